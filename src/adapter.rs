@@ -1,12 +1,16 @@
-use crate::{ReadAt, WriteAt};
+use crate::{ReadAt, Size, WriteAt};
 use std::io;
 
 /// An adapter that implement `std::io` traits.
 ///
-/// This type works by maintaining its own cursor.
+/// This type works by maintaining its own cursor. It also counts the bytes
+/// transferred through its `Read` and `Write` implementations, which can be
+/// used to report progress.
 #[derive(Debug, Clone, Copy)]
 pub struct Adapter<T: ?Sized> {
     offset: u64,
+    total_read: u64,
+    total_written: u64,
     inner: T,
 }
 
@@ -14,7 +18,38 @@ impl<T> Adapter<T> {
     /// Creates a new `Adapter`.
     #[inline]
     pub fn new(inner: T) -> Self {
-        Self { offset: 0, inner }
+        Self::with_offset(inner, 0)
+    }
+
+    /// Creates a new `Adapter` with its cursor at the given offset.
+    ///
+    /// The offset may be past the end of the stream, in which case reads will
+    /// return short.
+    #[inline]
+    pub fn with_offset(inner: T, offset: u64) -> Self {
+        Self {
+            offset,
+            total_read: 0,
+            total_written: 0,
+            inner,
+        }
+    }
+
+    /// Creates a new `Adapter` from a cursor, keeping its position.
+    #[inline]
+    pub fn from_cursor(cursor: io::Cursor<T>) -> Self {
+        let offset = cursor.position();
+        Self::with_offset(cursor.into_inner(), offset)
+    }
+
+    /// Converts this adapter into a cursor at the same position.
+    ///
+    /// The counters of the adapter are lost.
+    #[inline]
+    pub fn into_cursor(self) -> io::Cursor<T> {
+        let mut cursor = io::Cursor::new(self.inner);
+        cursor.set_position(self.offset);
+        cursor
     }
 
     /// Unwraps the inner stream.
@@ -35,6 +70,40 @@ impl<T: ?Sized> Adapter<T> {
         self.offset
     }
 
+    /// Returns the total number of bytes read through [`io::Read`].
+    ///
+    /// Unlike the offset, this is not affected by seeks.
+    #[must_use]
+    #[inline]
+    pub fn total_read(&self) -> u64 {
+        self.total_read
+    }
+
+    /// Returns the total number of bytes written through [`io::Write`].
+    ///
+    /// Unlike the offset, this is not affected by seeks.
+    #[must_use]
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.total_written
+    }
+
+    /// Resets the counters returned by [`total_read`](Self::total_read) and
+    /// [`total_written`](Self::total_written) to zero.
+    #[inline]
+    pub fn reset_counters(&mut self) {
+        self.total_read = 0;
+        self.total_written = 0;
+    }
+
+    /// Advances the cursor after `n` bytes were read from it by other means,
+    /// and counts them as read.
+    #[inline]
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.offset += n as u64;
+        self.total_read += n as u64;
+    }
+
     /// Gets a reference to the underlying stream.
     #[inline]
     pub fn get_ref(&self) -> &T {
@@ -48,6 +117,20 @@ impl<T: ?Sized> Adapter<T> {
     }
 }
 
+impl<T: Size + ?Sized> Adapter<T> {
+    /// Seeks to an offset relative to the end of the stream, and returns the
+    /// new offset.
+    ///
+    /// This queries the current size of the stream, so it takes into account
+    /// writes made through this adapter or elsewhere. Seeking past the end of
+    /// the stream is allowed: a subsequent write there leaves a hole between
+    /// the previous end and the written bytes, which reads as zeros for files.
+    pub fn seek_from_end(&mut self, offset: i64) -> io::Result<u64> {
+        self.offset = add_signed(self.inner.size()?, offset)?;
+        Ok(self.offset)
+    }
+}
+
 impl<T> ReadAt for Adapter<T>
 where
     T: ReadAt + ?Sized,
@@ -91,6 +174,26 @@ where
     fn flush(&self) -> io::Result<()> {
         self.inner.flush()
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<T> Size for Adapter<T>
+where
+    T: Size + ?Sized,
+{
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
 }
 
 impl<T> io::Read for Adapter<T>
@@ -101,6 +204,7 @@ where
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let read = self.inner.read_at(buf, self.offset)?;
         self.offset += read as u64;
+        self.total_read += read as u64;
         Ok(read)
     }
 
@@ -109,6 +213,7 @@ where
         let ret = self.inner.read_exact_at(buf, self.offset);
         if ret.is_ok() {
             self.offset += buf.len() as u64;
+            self.total_read += buf.len() as u64;
         }
         ret
     }
@@ -117,6 +222,7 @@ where
     fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
         let read = self.inner.read_vectored_at(bufs, self.offset)?;
         self.offset += read as u64;
+        self.total_read += read as u64;
         Ok(read)
     }
 }
@@ -129,6 +235,7 @@ where
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let written = self.inner.write_at(buf, self.offset)?;
         self.offset += written as u64;
+        self.total_written += written as u64;
         Ok(written)
     }
 
@@ -137,6 +244,7 @@ where
         let ret = self.inner.write_all_at(buf, self.offset);
         if ret.is_ok() {
             self.offset += buf.len() as u64;
+            self.total_written += buf.len() as u64;
         }
         ret
     }
@@ -145,6 +253,7 @@ where
     fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
         let written = self.inner.write_vectored_at(bufs, self.offset)?;
         self.offset += written as u64;
+        self.total_written += written as u64;
         Ok(written)
     }
 
@@ -158,18 +267,13 @@ impl<T> io::Seek for Adapter<T>
 where
     T: ?Sized,
 {
-    /// Note: seeking to an offset relative to the end of a stream is unsupported.
+    /// Note: seeking to an offset relative to the end of a stream is
+    /// unsupported, use [`seek_from_end`](Adapter::seek_from_end) instead.
     #[inline]
     fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
         self.offset = match pos {
             io::SeekFrom::Start(p) => p,
-            io::SeekFrom::Current(p) => {
-                let (offset, overflowed) = self.offset.overflowing_add(p as u64);
-                if overflowed ^ (p < 0) {
-                    return Err(invalid_seek());
-                }
-                offset
-            }
+            io::SeekFrom::Current(p) => add_signed(self.offset, p)?,
             io::SeekFrom::End(_) => return Err(unsupported()),
         };
 
@@ -188,6 +292,15 @@ where
     }
 }
 
+#[inline]
+fn add_signed(base: u64, offset: i64) -> io::Result<u64> {
+    let (result, overflowed) = base.overflowing_add(offset as u64);
+    if overflowed ^ (offset < 0) {
+        return Err(invalid_seek());
+    }
+    Ok(result)
+}
+
 #[cold]
 fn invalid_seek() -> io::Error {
     io::Error::new(
@@ -203,3 +316,163 @@ fn unsupported() -> io::Error {
         "unsupported seek to end of stream",
     )
 }
+
+/// A reader that always reads from the same offset.
+///
+/// **This reader never advances**: unlike [`Adapter`], every call to `read`
+/// reads from the offset it was created with, so calling it twice returns the
+/// same bytes. This makes retrying a read as simple as calling `read` again.
+///
+/// `read_to_end` and `read_to_string` read everything from the offset to the
+/// end of the stream, and do not advance either.
+///
+/// This type is created by [`ReadAt::reader_fixed`].
+#[derive(Debug)]
+pub struct FixedReader<'a, R: ?Sized> {
+    inner: &'a R,
+    offset: u64,
+}
+
+impl<'a, R: ?Sized> FixedReader<'a, R> {
+    /// Creates a new `FixedReader` reading from `offset`.
+    #[inline]
+    pub fn new(inner: &'a R, offset: u64) -> Self {
+        Self { inner, offset }
+    }
+
+    /// Returns the offset this reader reads from.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Gets a reference to the underlying stream.
+    #[inline]
+    pub fn get_ref(&self) -> &'a R {
+        self.inner
+    }
+}
+
+impl<R: ?Sized> Clone for FixedReader<'_, R> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R: ?Sized> Copy for FixedReader<'_, R> {}
+
+impl<R> io::Read for FixedReader<'_, R>
+where
+    R: ReadAt + ?Sized,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read_at(buf, self.offset)
+    }
+
+    #[inline]
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact_at(buf, self.offset)
+    }
+
+    #[inline]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.inner.read_vectored_at(bufs, self.offset)
+    }
+
+    // The default implementations would loop forever, as `read` never reaches
+    // the end of the stream.
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut adapter = Adapter::with_offset(self.inner, self.offset);
+        adapter.read_to_end(buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        let mut adapter = Adapter::with_offset(self.inner, self.offset);
+        adapter.read_to_string(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Seek, Write};
+
+    #[test]
+    fn cursor_round_trip() {
+        let mut cursor = io::Cursor::new(b"Hello World!".to_vec());
+        cursor.set_position(6);
+
+        let mut adapter = Adapter::from_cursor(cursor);
+        assert_eq!(adapter.offset(), 6);
+        let mut buf = [0; 5];
+        adapter.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"World");
+
+        let cursor = adapter.into_cursor();
+        assert_eq!(cursor.position(), 11);
+        assert_eq!(cursor.get_ref(), b"Hello World!");
+    }
+
+    #[test]
+    fn counters() {
+        let mut adapter = Adapter::new(crate::RandomAccessFile::open("LICENSE-MIT").unwrap());
+        let mut buf = [0; 10];
+
+        adapter.read_exact(&mut buf).unwrap();
+        adapter.seek(io::SeekFrom::Start(4)).unwrap();
+        adapter.read_exact(&mut buf[..5]).unwrap();
+        assert_eq!(adapter.total_read(), 15);
+        assert_eq!(adapter.offset(), 9);
+
+        adapter.reset_counters();
+        assert_eq!(adapter.total_read(), 0);
+
+        let mut adapter = Adapter::new(crate::tests::MemFile::default());
+        adapter.write_all(b"Hello").unwrap();
+        adapter.seek(io::SeekFrom::Start(0)).unwrap();
+        assert_eq!(adapter.write(b"World").unwrap(), 5);
+        assert_eq!(adapter.total_written(), 10);
+        assert_eq!(adapter.offset(), 5);
+    }
+
+    #[test]
+    fn seek_from_end() {
+        let mut adapter = Adapter::new(crate::tests::MemFile::default());
+        adapter.write_all(b"Hello").unwrap();
+        adapter.rewind().unwrap();
+        adapter.get_ref().write_all_at(b" World", 5).unwrap();
+
+        assert_eq!(adapter.seek_from_end(0).unwrap(), 11);
+        adapter.write_all(b"!").unwrap();
+        assert_eq!(adapter.get_ref().contents(), b"Hello World!");
+
+        assert_eq!(adapter.seek_from_end(2).unwrap(), 14);
+        adapter.write_all(b"?").unwrap();
+        assert_eq!(adapter.get_ref().contents(), b"Hello World!\0\0?");
+
+        assert!(adapter.seek_from_end(-16).is_err());
+        let err = adapter.seek(io::SeekFrom::End(0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn fixed_reader_does_not_advance() {
+        let data = b"Hello World!";
+        let mut reader = data.reader_fixed(6);
+
+        let mut buf = [0; 5];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"World");
+        buf = [0; 5];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"World");
+        assert_eq!(reader.offset(), 6);
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "World!");
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    io,
+    ops::Range,
+    sync::{Condvar, Mutex, PoisonError},
+};
+
+/// A writer that turns unaligned writes into whole-block writes.
+///
+/// Some targets, such as block devices or files opened with `O_DIRECT`, only
+/// accept writes that start and end on a block boundary. `AutoAlign` extends
+/// each write to the blocks it touches: the partial blocks at its head and
+/// tail are read from the target, merged with the new data, and the whole
+/// blocks are written back.
+///
+/// This read-modify-write is atomic per block: concurrent writes that share a
+/// block through the same `AutoAlign` wait for each other, while writes to
+/// disjoint blocks run in parallel. Writes made to the target by other means
+/// are not synchronized.
+///
+/// Reading the tail block past the end of the target yields zeros, so a write
+/// that ends in the middle of the last block extends the target to the end of
+/// that block.
+///
+/// Reads are forwarded to the target as is.
+#[derive(Debug)]
+pub struct AutoAlign<W> {
+    inner: W,
+    block_size: u64,
+    locks: BlockLocks,
+}
+
+impl<W> AutoAlign<W> {
+    /// Creates a new `AutoAlign` writer for blocks of `block_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn new(inner: W, block_size: u64) -> Self {
+        assert!(block_size != 0, "block size must be non-zero");
+
+        Self {
+            inner,
+            block_size,
+            locks: BlockLocks::default(),
+        }
+    }
+
+    /// Returns the configured block size.
+    #[must_use]
+    #[inline]
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: ReadAt + WriteAt> AutoAlign<W> {
+    /// Fills `buf` from `offset`, with zeros past the end of the target.
+    fn read_block(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.inner.read_at(&mut buf[read..], offset + read as u64) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        buf[read..].fill(0);
+        Ok(())
+    }
+}
+
+impl<W: ReadAt> ReadAt for AutoAlign<W> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<W: Size> Size for AutoAlign<W> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+impl<W: ReadAt + WriteAt> WriteAt for AutoAlign<W> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let bs = self.block_size;
+        let start = offset / bs * bs;
+        let end = offset
+            .checked_add(buf.len() as u64)
+            .and_then(|end| end.checked_add(bs - 1))
+            .map(|end| end / bs * bs)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "write past the maximum offset")
+            })?;
+
+        let _guard = self.locks.lock(start / bs..end / bs);
+
+        if start == offset && end == offset + buf.len() as u64 {
+            return self.inner.write_all_at(buf, offset);
+        }
+
+        let len = usize::try_from(end - start).map_err(|_| {
+            io::Error::new(io::ErrorKind::OutOfMemory, "write too large to be aligned")
+        })?;
+        let mut blocks = vec![0; len];
+        let head = (offset - start) as usize;
+        let tail = head + buf.len();
+
+        if head != 0 {
+            self.read_block(&mut blocks[..bs as usize], start)?;
+        }
+        if tail != len && (head == 0 || len > bs as usize) {
+            self.read_block(&mut blocks[len - bs as usize..], end - bs)?;
+        }
+        blocks[head..tail].copy_from_slice(buf);
+
+        self.inner.write_all_at(&blocks, start)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+/// Ranges of blocks currently held by a writer.
+#[derive(Debug, Default)]
+struct BlockLocks {
+    held: Mutex<Vec<Range<u64>>>,
+    released: Condvar,
+}
+
+impl BlockLocks {
+    /// Waits until no block of `blocks` is held, then holds them until the
+    /// guard is dropped.
+    fn lock(&self, blocks: Range<u64>) -> BlockGuard<'_> {
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        while held
+            .iter()
+            .any(|r| r.start < blocks.end && blocks.start < r.end)
+        {
+            held = self
+                .released
+                .wait(held)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        held.push(blocks.clone());
+
+        BlockGuard {
+            locks: self,
+            blocks,
+        }
+    }
+}
+
+struct BlockGuard<'a> {
+    locks: &'a BlockLocks,
+    blocks: Range<u64>,
+}
+
+impl Drop for BlockGuard<'_> {
+    fn drop(&mut self) {
+        let mut held = self
+            .locks
+            .held
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(pos) = held.iter().position(|r| *r == self.blocks) {
+            held.swap_remove(pos);
+        }
+        drop(held);
+        self.locks.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+    use std::sync::Arc;
+
+    /// A target that rejects unaligned writes.
+    #[derive(Default)]
+    struct Aligned(MemFile);
+
+    impl ReadAt for Aligned {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            self.0.read_at(buf, offset)
+        }
+    }
+
+    impl WriteAt for Aligned {
+        fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+            if offset % 4 != 0 || buf.len() % 4 != 0 {
+                return Err(io::ErrorKind::InvalidInput.into());
+            }
+            self.0.write_at(buf, offset)
+        }
+    }
+
+    #[test]
+    fn unaligned_write() {
+        let writer = AutoAlign::new(Aligned::default(), 4);
+        writer.write_all_at(b"0123456789abcdef", 0).unwrap();
+
+        writer.write_all_at(b"XXXXXXX", 3).unwrap();
+        assert_eq!(writer.get_ref().0.contents(), b"012XXXXXXXabcdef");
+
+        writer.write_all_at(b"YY", 5).unwrap();
+        assert_eq!(writer.get_ref().0.contents(), b"012XXYYXXXabcdef");
+
+        writer.write_all_at(b"!", 17).unwrap();
+        assert_eq!(writer.get_ref().0.contents(), b"012XXYYXXXabcdef\0!\0\0");
+    }
+
+    #[test]
+    fn concurrent_writes() {
+        let writer = Arc::new(AutoAlign::new(Aligned::default(), 8));
+        writer.write_all_at(&[b'.'; 64], 0).unwrap();
+
+        let threads: Vec<_> = (0..16u8)
+            .map(|i| {
+                let writer = writer.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        writer.write_all_at(&[b'a' + i; 3], i as u64 * 3).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let contents = writer.get_ref().0.contents();
+        for i in 0..16u8 {
+            let pos = i as usize * 3;
+            assert_eq!(contents[pos..pos + 3], [b'a' + i; 3]);
+        }
+    }
+}
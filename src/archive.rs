@@ -0,0 +1,314 @@
+use crate::{ReadAt, Size};
+use std::{cmp::min, io};
+
+const BLOCK_SIZE: u64 = 512;
+/// The maximum length of a GNU long name, which is read in memory.
+const MAX_LONG_NAME: u64 = 64 * 1024;
+
+/// A read-only view of a member of an archive.
+///
+/// This is a window of `len` bytes starting at `offset` in the archive: reads
+/// are translated to the archive, and never go past the end of the member.
+/// Members are usually obtained with [`tar_entries`], but any uncompressed
+/// member can be viewed if its position is known.
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveMember<R> {
+    archive: R,
+    offset: u64,
+    len: u64,
+}
+
+impl<R> ArchiveMember<R> {
+    /// Creates a view of the `len` bytes at `offset` in `archive`.
+    #[inline]
+    pub fn new(archive: R, offset: u64, len: u64) -> Self {
+        Self {
+            archive,
+            offset,
+            len,
+        }
+    }
+
+    /// Returns the offset of the member in the archive.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the length of the member.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the member is empty.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets a reference to the underlying archive.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.archive
+    }
+
+    /// Unwraps the underlying archive.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.archive
+    }
+}
+
+impl<R: ReadAt> ReadAt for ArchiveMember<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(offset);
+        let len = min(buf.len() as u64, remaining) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+        self.archive.read_at(&mut buf[..len], self.offset + offset)
+    }
+}
+
+impl<R> Size for ArchiveMember<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
+
+/// A regular file in a tar archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarEntry {
+    path: String,
+    offset: u64,
+    len: u64,
+}
+
+impl TarEntry {
+    /// Returns the path of the file in the archive.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the offset of the contents of the file in the archive.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the length of the file.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the file is empty.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a view of the file in `archive`.
+    #[inline]
+    pub fn member<R>(&self, archive: R) -> ArchiveMember<R> {
+        ArchiveMember::new(archive, self.offset, self.len)
+    }
+}
+
+/// Lists the regular files of an uncompressed tar archive.
+///
+/// Ustar path prefixes and GNU long names are supported. Other extensions,
+/// such as PAX headers, are skipped, and entries that are not regular files
+/// are not listed.
+///
+/// # Errors
+///
+/// Returns an error of kind [`io::ErrorKind::InvalidData`] if a header is
+/// invalid, or if a GNU long name is longer than 64 KiB.
+pub fn tar_entries<R: ReadAt + ?Sized>(archive: &R) -> io::Result<Vec<TarEntry>> {
+    let mut entries = Vec::new();
+    let mut long_name = None;
+    let mut offset = 0u64;
+
+    loop {
+        let mut header = [0; BLOCK_SIZE as usize];
+        archive.read_exact_at(&mut header, offset)?;
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let checksum = parse_number(&header[148..156])?;
+        let sum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+            .sum();
+        if sum != checksum {
+            return Err(invalid_header("invalid tar header checksum"));
+        }
+
+        let len = parse_number(&header[124..136])?;
+        let data = offset + BLOCK_SIZE;
+        offset = len
+            .checked_add(BLOCK_SIZE - 1)
+            .map(|len| len / BLOCK_SIZE * BLOCK_SIZE)
+            .and_then(|len| data.checked_add(len))
+            .ok_or_else(|| invalid_header("invalid tar entry size"))?;
+
+        match header[156] {
+            b'L' => {
+                if len > MAX_LONG_NAME {
+                    return Err(invalid_header("tar long name too long"));
+                }
+                let mut name = vec![0; len as usize];
+                archive.read_exact_at(&mut name, data)?;
+                long_name = Some(name);
+            }
+            b'0' | b'\0' | b'7' => {
+                let path = match long_name.take() {
+                    Some(name) => String::from_utf8_lossy(until_nul(&name)).into_owned(),
+                    None => header_path(&header),
+                };
+                entries.push(TarEntry {
+                    path,
+                    offset: data,
+                    len,
+                });
+            }
+            _ => long_name = None,
+        }
+    }
+
+    Ok(entries)
+}
+
+fn until_nul(bytes: &[u8]) -> &[u8] {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    &bytes[..end]
+}
+
+fn header_path(header: &[u8; BLOCK_SIZE as usize]) -> String {
+    let name = until_nul(&header[0..100]);
+    let prefix = until_nul(&header[345..500]);
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        let mut path = prefix.to_vec();
+        path.push(b'/');
+        path.extend_from_slice(name);
+        String::from_utf8_lossy(&path).into_owned()
+    } else {
+        String::from_utf8_lossy(name).into_owned()
+    }
+}
+
+/// Parses a numeric field, either in octal or in GNU base-256.
+fn parse_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut value = (field[0] & 0x7f) as u64;
+        for &b in &field[1..] {
+            value = value
+                .checked_mul(256)
+                .ok_or_else(|| invalid_header("invalid tar number"))?
+                | b as u64;
+        }
+        return Ok(value);
+    }
+
+    let digits = until_nul(field);
+    let digits = std::str::from_utf8(digits)
+        .map_err(|_| invalid_header("invalid tar number"))?
+        .trim_matches(' ');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| invalid_header("invalid tar number"))
+}
+
+#[cold]
+fn invalid_header(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str, len: usize, kind: u8) -> [u8; 512] {
+        let mut header = [0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{len:011o}").as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        header
+    }
+
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = Vec::new();
+        tar.extend_from_slice(&header("dir/", 0, b'5'));
+        for (name, contents) in files {
+            tar.extend_from_slice(&header(name, contents.len(), b'0'));
+            tar.extend_from_slice(contents);
+            tar.resize((tar.len() + 511) / 512 * 512, 0);
+        }
+        tar.resize(tar.len() + 1024, 0);
+        tar
+    }
+
+    #[test]
+    fn read_members() {
+        let tar = tar(&[("dir/hello.txt", b"Hello World!"), ("dir/empty", b"")]);
+
+        let entries = tar_entries(&tar).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path(), "dir/hello.txt");
+        assert_eq!(entries[1].path(), "dir/empty");
+        assert!(entries[1].is_empty());
+
+        let member = entries[0].member(&tar);
+        assert_eq!(member.size().unwrap(), 12);
+        let mut buf = [0; 20];
+        assert_eq!(member.read_at(&mut buf, 6).unwrap(), 6);
+        assert_eq!(&buf[..6], b"World!");
+        assert_eq!(member.read_at(&mut buf, 12).unwrap(), 0);
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let mut tar = tar(&[("hello.txt", b"Hello World!")]);
+        tar[512] = b'j';
+
+        let err = tar_entries(&tar).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn long_name_too_long() {
+        let mut tar = header("././@LongLink", 1 << 30, b'L').to_vec();
+        tar.resize(tar.len() + 1024, 0);
+
+        let err = tar_entries(&tar).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    cmp::min,
+    convert::TryInto,
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        PoisonError, RwLock,
+    },
+};
+
+/// A fixed-capacity region of memory, with a bump allocator.
+///
+/// Regions are reserved with [`alloc`](Self::alloc), then written and read
+/// with positional I/O. The allocator is a single atomic cursor, so regions
+/// can be reserved concurrently from several threads. Reads can run in
+/// parallel, but writes are serialized.
+///
+/// The arena does not grow: writes past its capacity are short, and reads
+/// past it return zero bytes.
+#[derive(Debug)]
+pub struct Arena {
+    data: RwLock<Box<[u8]>>,
+    capacity: usize,
+    cursor: AtomicUsize,
+}
+
+impl Arena {
+    /// Creates a new zeroed `Arena` with the given capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self::from(vec![0; capacity].into_boxed_slice())
+    }
+
+    /// Reserves a region of `len` bytes, and returns its offset.
+    ///
+    /// Returns `None` if there is not enough space left in the arena.
+    pub fn alloc(&self, len: usize) -> Option<u64> {
+        let start = self
+            .cursor
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |cursor| {
+                cursor.checked_add(len).filter(|&end| end <= self.capacity)
+            })
+            .ok()?;
+        Some(start as u64)
+    }
+
+    /// Returns the capacity of the arena.
+    #[must_use]
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bytes reserved so far.
+    #[must_use]
+    #[inline]
+    pub fn allocated(&self) -> usize {
+        self.cursor.load(Ordering::Relaxed)
+    }
+
+    /// Unwraps the underlying memory.
+    #[inline]
+    pub fn into_inner(self) -> Box<[u8]> {
+        self.data
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<Box<[u8]>> for Arena {
+    /// Creates an `Arena` from existing memory. Nothing is reserved yet.
+    #[inline]
+    fn from(data: Box<[u8]>) -> Self {
+        Self {
+            capacity: data.len(),
+            data: RwLock::new(data),
+            cursor: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl ReadAt for Arena {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let data = self.data.read().unwrap_or_else(PoisonError::into_inner);
+        data.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let data = self.data.read().unwrap_or_else(PoisonError::into_inner);
+        data.read_exact_at(buf, offset)
+    }
+}
+
+impl WriteAt for Arena {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let mut data = self.data.write().unwrap_or_else(PoisonError::into_inner);
+        let dest = match offset
+            .try_into()
+            .ok()
+            .and_then(|o: usize| data.get_mut(o..))
+        {
+            Some(dest) => dest,
+            None => return Ok(0),
+        };
+
+        let len = min(dest.len(), buf.len());
+        dest[..len].copy_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        Ok(self.capacity as u64)
+    }
+}
+
+impl Size for Arena {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.capacity as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.capacity as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn concurrent_allocations() {
+        let arena = Arc::new(Arena::new(16));
+
+        let handles: Vec<_> = [*b"Hello", *b"World"]
+            .iter()
+            .map(|&msg| {
+                let arena = arena.clone();
+                std::thread::spawn(move || {
+                    let offset = arena.alloc(msg.len()).unwrap();
+                    arena.write_all_at(&msg, offset).unwrap();
+                    (offset, msg)
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let (offset, msg) = handle.join().unwrap();
+            let mut buf = [0; 5];
+            arena.read_exact_at(&mut buf, offset).unwrap();
+            assert_eq!(buf, msg);
+        }
+
+        assert_eq!(arena.allocated(), 10);
+        assert_eq!(arena.alloc(7), None);
+        assert_eq!(arena.alloc(6), Some(10));
+        assert_eq!(arena.write_at(b"Hello", 14).unwrap(), 2);
+        assert_eq!(arena.write_at(b"Hello", 16).unwrap(), 0);
+    }
+}
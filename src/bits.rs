@@ -0,0 +1,342 @@
+use crate::{ReadAt, WriteAt};
+use std::{
+    io,
+    sync::{Mutex, PoisonError},
+};
+
+/// The order of bits in a bit-packed source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Bits are numbered from the most significant bit of each byte, and
+    /// fields are stored most significant bit first.
+    ///
+    /// This is the order of most network protocols and of bitmaps drawn
+    /// left to right.
+    MsbFirst,
+    /// Bits are numbered from the least significant bit of each byte, and
+    /// fields are stored least significant bit first.
+    ///
+    /// This is the order used by DEFLATE and most little-endian formats.
+    LsbFirst,
+}
+
+/// A reader of bit fields of a bit-packed source.
+///
+/// Fields are addressed by their offset in bits from the start of the source,
+/// and may span byte boundaries. Each read reads the bytes covering the field
+/// from the inner reader.
+#[derive(Debug, Clone, Copy)]
+pub struct BitReader<R> {
+    inner: R,
+    order: BitOrder,
+}
+
+impl<R> BitReader<R> {
+    /// Creates a new `BitReader` with the given bit order.
+    #[inline]
+    pub fn new(inner: R, order: BitOrder) -> Self {
+        Self { inner, order }
+    }
+
+    /// Returns the bit order of the source.
+    #[must_use]
+    #[inline]
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ReadAt> BitReader<R> {
+    /// Reads the field of `num_bits` bits at `bit_offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if
+    /// `num_bits` is greater than 64, and of kind
+    /// [`io::ErrorKind::UnexpectedEof`] if the field is not entirely within
+    /// the source.
+    pub fn read_bits(&self, bit_offset: u64, num_bits: u32) -> io::Result<u64> {
+        let span = Span::new(bit_offset, num_bits)?;
+        let mut bytes = [0; 9];
+        let bytes = &mut bytes[..span.len];
+        self.inner.read_exact_at(bytes, span.offset)?;
+        Ok(span.extract(bytes, self.order))
+    }
+}
+
+/// A writer of bit fields of a bit-packed source.
+///
+/// Fields are addressed like with [`BitReader`], so a field written with a
+/// bit order is read back by a `BitReader` with the same bit order.
+///
+/// Bytes holding bits of other fields are not disturbed: each write reads the
+/// bytes covering the field, sets its bits, and writes them back. These
+/// read-modify-write cycles are serialized by a lock, so concurrent writes
+/// of fields sharing a byte through the same `BitWriter` do not lose bits.
+/// Other writes to the inner writer are not protected.
+#[derive(Debug)]
+pub struct BitWriter<W> {
+    inner: W,
+    order: BitOrder,
+    lock: Mutex<()>,
+}
+
+impl<W> BitWriter<W> {
+    /// Creates a new `BitWriter` with the given bit order.
+    #[inline]
+    pub fn new(inner: W, order: BitOrder) -> Self {
+        Self {
+            inner,
+            order,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns the bit order of the source.
+    #[must_use]
+    #[inline]
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: ReadAt + WriteAt> BitWriter<W> {
+    /// Writes the low `num_bits` bits of `value` as the field at
+    /// `bit_offset`.
+    ///
+    /// Higher bits of `value` are ignored. Bytes past the end of the source
+    /// are read as zeros, so fields can be written to grow it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if
+    /// `num_bits` is greater than 64.
+    pub fn write_bits(&self, bit_offset: u64, value: u64, num_bits: u32) -> io::Result<()> {
+        let span = Span::new(bit_offset, num_bits)?;
+        let mut bytes = [0; 9];
+        let bytes = &mut bytes[..span.len];
+
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match self
+                .inner
+                .read_at(&mut bytes[filled..], span.offset + filled as u64)
+            {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        span.insert(bytes, value, self.order);
+        self.inner.write_all_at(bytes, span.offset)
+    }
+}
+
+/// The bytes covering a bit field.
+struct Span {
+    /// The offset of the first byte.
+    offset: u64,
+    /// The number of bytes.
+    len: usize,
+    /// The position of the field in the bytes.
+    shift: u32,
+    num_bits: u32,
+}
+
+impl Span {
+    fn new(bit_offset: u64, num_bits: u32) -> io::Result<Self> {
+        if num_bits > 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "bit fields are limited to 64 bits",
+            ));
+        }
+
+        let shift = (bit_offset % 8) as u32;
+        Ok(Self {
+            offset: bit_offset / 8,
+            len: ((shift + num_bits + 7) / 8) as usize,
+            shift,
+            num_bits,
+        })
+    }
+
+    /// Returns the position of the field in the integer made of the covering
+    /// bytes, and the mask of its bits once shifted down.
+    fn mask(&self, order: BitOrder) -> (u32, u128) {
+        let pos = match order {
+            BitOrder::MsbFirst => self.len as u32 * 8 - self.shift - self.num_bits,
+            BitOrder::LsbFirst => self.shift,
+        };
+        (pos, (1u128 << self.num_bits) - 1)
+    }
+
+    fn extract(&self, bytes: &[u8], order: BitOrder) -> u64 {
+        let (pos, mask) = self.mask(order);
+        ((to_int(bytes, order) >> pos) & mask) as u64
+    }
+
+    fn insert(&self, bytes: &mut [u8], value: u64, order: BitOrder) {
+        let (pos, mask) = self.mask(order);
+        let int = to_int(bytes, order) & !(mask << pos) | (value as u128 & mask) << pos;
+
+        let len = bytes.len();
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let shift = match order {
+                BitOrder::MsbFirst => len - 1 - i,
+                BitOrder::LsbFirst => i,
+            };
+            *byte = (int >> (8 * shift)) as u8;
+        }
+    }
+}
+
+/// Concatenates up to 16 bytes in an integer, in the byte order matching
+/// `order`.
+fn to_int(bytes: &[u8], order: BitOrder) -> u128 {
+    match order {
+        BitOrder::MsbFirst => bytes.iter().fold(0, |acc, &b| acc << 8 | b as u128),
+        BitOrder::LsbFirst => bytes.iter().rev().fold(0, |acc, &b| acc << 8 | b as u128),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    const DATA: &[u8] = &[
+        0xb5, 0x3c, 0x01, 0xfe, 0x7a, 0x96, 0x00, 0xff, 0x42, 0xd9, 0x18, 0xe7,
+    ];
+
+    /// Reads a field bit by bit.
+    fn manual(order: BitOrder, bit_offset: u64, num_bits: u32) -> u64 {
+        let bit = |i: u64| {
+            let byte = DATA[(i / 8) as usize];
+            match order {
+                BitOrder::MsbFirst => (byte >> (7 - i % 8)) & 1,
+                BitOrder::LsbFirst => (byte >> (i % 8)) & 1,
+            }
+        };
+
+        (0..num_bits as u64).fold(0, |value, i| {
+            let bit = bit(bit_offset + i) as u64;
+            match order {
+                BitOrder::MsbFirst => value << 1 | bit,
+                BitOrder::LsbFirst => value | bit << i,
+            }
+        })
+    }
+
+    #[test]
+    fn read_bits() {
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            let reader = BitReader::new(DATA, order);
+            for num_bits in [0, 1, 3, 7, 8, 9, 13, 31, 57, 63, 64] {
+                for bit_offset in 0..=(DATA.len() as u64 * 8 - num_bits as u64) {
+                    assert_eq!(
+                        reader.read_bits(bit_offset, num_bits).unwrap(),
+                        manual(order, bit_offset, num_bits),
+                        "{order:?} {bit_offset} {num_bits}",
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        let reader = BitReader::new(DATA, BitOrder::MsbFirst);
+        assert_eq!(reader.read_bits(0, 4).unwrap(), 0xb);
+        assert_eq!(reader.read_bits(4, 8).unwrap(), 0x53);
+        assert_eq!(reader.read_bits(20, 12).unwrap(), 0x1fe);
+
+        let reader = BitReader::new(DATA, BitOrder::LsbFirst);
+        assert_eq!(reader.read_bits(0, 4).unwrap(), 0x5);
+        assert_eq!(reader.read_bits(4, 8).unwrap(), 0xcb);
+        assert_eq!(reader.read_bits(3, 64).unwrap(), 0x5fe0_12cf_5fc0_2796);
+    }
+
+    #[test]
+    fn round_trip() {
+        let fields = [
+            (0, 0b101, 3),
+            (3, 0x1f, 5),
+            (8, 0, 1),
+            (9, 0x1234, 13),
+            (22, u64::MAX, 64),
+            (86, 0x2a, 7),
+            (93, 1, 1),
+        ];
+
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            let writer = BitWriter::new(MemFile::default(), order);
+            for &(bit_offset, value, num_bits) in &fields {
+                writer.write_bits(bit_offset, value, num_bits).unwrap();
+            }
+            // High bits of the value are ignored.
+            writer.write_bits(94, 0xf0, 2).unwrap();
+
+            let reader = BitReader::new(writer.into_inner(), order);
+            for &(bit_offset, value, num_bits) in &fields {
+                assert_eq!(reader.read_bits(bit_offset, num_bits).unwrap(), value);
+            }
+            assert_eq!(reader.read_bits(94, 2).unwrap(), 0);
+            assert_eq!(reader.get_ref().contents().len(), 12);
+        }
+    }
+
+    #[test]
+    fn keeps_neighbors() {
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            let writer = BitWriter::new(MemFile::default(), order);
+            writer.get_ref().write_all_at(&[0xff; 2], 0).unwrap();
+            writer.write_bits(4, 0b0110, 4).unwrap();
+            writer.write_bits(9, 0, 2).unwrap();
+
+            let reader = BitReader::new(writer.into_inner(), order);
+            assert_eq!(reader.read_bits(0, 4).unwrap(), 0xf);
+            assert_eq!(reader.read_bits(4, 4).unwrap(), 0b0110);
+            assert_eq!(reader.read_bits(8, 1).unwrap(), 1);
+            assert_eq!(reader.read_bits(9, 2).unwrap(), 0);
+            assert_eq!(reader.read_bits(11, 5).unwrap(), 0x1f);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let reader = BitReader::new(DATA, BitOrder::MsbFirst);
+        let err = reader.read_bits(0, 65).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = reader.read_bits(90, 7).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::io;
+
+/// A block-addressed view of a byte-addressed source.
+///
+/// Blocks are numbered by their logical block address (LBA), and the block
+/// `lba` is stored at the byte offset `lba * block_size`. Reads and writes
+/// transfer whole blocks, and fail with [`io::ErrorKind::InvalidInput`] if the
+/// buffer is not exactly one block long.
+///
+/// Reading a block past the end of the source fails with
+/// [`io::ErrorKind::UnexpectedEof`], while writing one extends the source.
+#[derive(Debug)]
+pub struct BlockDevice<R> {
+    inner: R,
+    block_size: u64,
+}
+
+impl<R> BlockDevice<R> {
+    /// Creates a new `BlockDevice` with blocks of `block_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn new(inner: R, block_size: u64) -> Self {
+        assert!(block_size != 0, "block size must be non-zero");
+
+        Self { inner, block_size }
+    }
+
+    /// Returns the size of blocks.
+    #[must_use]
+    #[inline]
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the byte offset of the block `lba`, checking that `len` is the
+    /// size of a block.
+    fn block_offset(&self, lba: u64, len: usize) -> io::Result<u64> {
+        if len as u64 != self.block_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "buffer size is not the block size",
+            ));
+        }
+        lba.checked_mul(self.block_size)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "block address overflow"))
+    }
+}
+
+impl<R: ReadAt> BlockDevice<R> {
+    /// Reads the block `lba` into `buf`.
+    pub fn read_block(&self, lba: u64, buf: &mut [u8]) -> io::Result<()> {
+        let offset = self.block_offset(lba, buf.len())?;
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<R: WriteAt> BlockDevice<R> {
+    /// Writes `buf` to the block `lba`.
+    pub fn write_block(&self, lba: u64, buf: &[u8]) -> io::Result<()> {
+        let offset = self.block_offset(lba, buf.len())?;
+        self.inner.write_all_at(buf, offset)
+    }
+
+    /// Flushes the underlying source.
+    #[inline]
+    pub fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: Size> BlockDevice<R> {
+    /// Returns the number of complete blocks in the source.
+    pub fn num_blocks(&self) -> io::Result<u64> {
+        Ok(self.inner.size()? / self.block_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn blocks() {
+        let device = BlockDevice::new(MemFile::default(), 4);
+        device.write_block(1, b"1111").unwrap();
+        device.write_block(0, b"0000").unwrap();
+        device.write_block(3, b"DDDD").unwrap();
+        assert_eq!(device.num_blocks().unwrap(), 4);
+        assert_eq!(device.get_ref().contents(), b"00001111\0\0\0\0DDDD");
+
+        let mut buf = [0; 4];
+        device.read_block(1, &mut buf).unwrap();
+        assert_eq!(&buf, b"1111");
+        device.read_block(2, &mut buf).unwrap();
+        assert_eq!(&buf, b"\0\0\0\0");
+
+        let err = device.read_block(4, &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn wrong_buffer_size() {
+        let device = BlockDevice::new(MemFile::default(), 4);
+        let err = device.write_block(0, b"123").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = device.read_block(0, &mut [0; 8]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(device.num_blocks().unwrap(), 0);
+    }
+}
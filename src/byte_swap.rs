@@ -0,0 +1,140 @@
+use crate::{ReadAt, Size};
+use std::io;
+
+/// A reader that reverses the byte order of fixed-size words.
+///
+/// This presents data stored with the opposite endianness as native words,
+/// for example an array of big-endian `u32` as native `u32` on a
+/// little-endian host, so that it can be copied directly into integers.
+///
+/// Reads must be aligned to whole words: their offset and length must be
+/// multiples of the word size, or they fail with
+/// [`io::ErrorKind::InvalidInput`]. A read that would end in the middle of a
+/// word because the source is too short fails with
+/// [`io::ErrorKind::UnexpectedEof`].
+#[derive(Debug, Clone, Copy)]
+pub struct ByteSwapped<R> {
+    inner: R,
+    word_size: usize,
+}
+
+impl<R> ByteSwapped<R> {
+    /// Creates a new `ByteSwapped` reader with words of `word_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `word_size` is not 2, 4 or 8.
+    pub fn new(inner: R, word_size: usize) -> Self {
+        assert!(
+            matches!(word_size, 2 | 4 | 8),
+            "word size must be 2, 4 or 8"
+        );
+
+        Self { inner, word_size }
+    }
+
+    /// Returns the size of words.
+    #[must_use]
+    #[inline]
+    pub fn word_size(&self) -> usize {
+        self.word_size
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn check_aligned(&self, len: usize, offset: u64) -> io::Result<()> {
+        if len % self.word_size != 0 || offset % self.word_size as u64 != 0 {
+            return Err(unaligned());
+        }
+        Ok(())
+    }
+
+    fn swap(&self, buf: &mut [u8]) {
+        for word in buf.chunks_exact_mut(self.word_size) {
+            word.reverse();
+        }
+    }
+}
+
+impl<R: ReadAt> ReadAt for ByteSwapped<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.check_aligned(buf.len(), offset)?;
+
+        let mut n = self.inner.read_at(buf, offset)?;
+        let partial = n % self.word_size;
+        if partial != 0 {
+            let end = n - partial + self.word_size;
+            self.inner
+                .read_exact_at(&mut buf[n..end], offset + n as u64)?;
+            n = end;
+        }
+
+        self.swap(&mut buf[..n]);
+        Ok(n)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.check_aligned(buf.len(), offset)?;
+        self.inner.read_exact_at(buf, offset)?;
+        self.swap(buf);
+        Ok(())
+    }
+}
+
+impl<R: Size> Size for ByteSwapped<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cold]
+fn unaligned() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "read is not aligned to the word size",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_endian_u32() {
+        let values = [1u32, 0xdead_beef, 0x0102_0304, u32::MAX];
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let swapped = ByteSwapped::new(&data[..], 4);
+
+        let mut buf = [0; 12];
+        assert_eq!(swapped.read_at(&mut buf, 4).unwrap(), 12);
+        for (i, word) in buf.chunks_exact(4).enumerate() {
+            let expected = u32::from_be_bytes(data[4 + i * 4..][..4].try_into().unwrap());
+            assert_eq!(u32::from_le_bytes(word.try_into().unwrap()), expected);
+        }
+
+        let mut buf = [0; 4];
+        swapped.read_exact_at(&mut buf, 4).unwrap();
+        assert_eq!(u32::from_le_bytes(buf), 0xdead_beef);
+
+        let err = swapped.read_at(&mut buf, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = swapped.read_at(&mut buf[..3], 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{RandomAccessFile, ReadAt, Size, WriteAt};
+use std::{
+    fs, io,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// A file that caches its metadata.
+///
+/// [`metadata`](Self::metadata) queries the metadata of the file on its first
+/// call only, and returns the same value until
+/// [`refresh_metadata`](Self::refresh_metadata) is called. This saves a system
+/// call for workloads that check the metadata of a file they own frequently.
+///
+/// The cached metadata can be stale: it does not reflect changes made to the
+/// file since it was queried, even through this `CachedMetadata`. Use
+/// [`RandomAccessFile::metadata`] on [`get_ref`](Self::get_ref) to get
+/// up-to-date values.
+#[derive(Debug)]
+pub struct CachedMetadata {
+    file: RandomAccessFile,
+    metadata: Mutex<Option<fs::Metadata>>,
+}
+
+impl CachedMetadata {
+    /// Creates a new `CachedMetadata`, with an empty cache.
+    #[inline]
+    pub fn new(file: RandomAccessFile) -> Self {
+        Self {
+            file,
+            metadata: Mutex::new(None),
+        }
+    }
+
+    /// Returns the metadata of the file, querying it only on the first call.
+    pub fn metadata(&self) -> io::Result<fs::Metadata> {
+        let mut cached = self.lock();
+        match &*cached {
+            Some(metadata) => Ok(metadata.clone()),
+            None => Ok(cached.insert(self.file.metadata()?).clone()),
+        }
+    }
+
+    /// Queries the metadata of the file and updates the cached value.
+    pub fn refresh_metadata(&self) -> io::Result<fs::Metadata> {
+        let metadata = self.file.metadata()?;
+        *self.lock() = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Gets a reference to the underlying file.
+    #[inline]
+    pub fn get_ref(&self) -> &RandomAccessFile {
+        &self.file
+    }
+
+    /// Unwraps the underlying file.
+    #[inline]
+    pub fn into_inner(self) -> RandomAccessFile {
+        self.file
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Option<fs::Metadata>> {
+        self.metadata.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ReadAt for CachedMetadata {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.file.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.file.read_exact_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        self.file.read_vectored_at(bufs, offset)
+    }
+}
+
+impl WriteAt for CachedMetadata {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.file.write_at(buf, offset)
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.file.write_all_at(buf, offset)
+    }
+
+    #[inline]
+    fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        self.file.write_vectored_at(bufs, offset)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.file.flush_tracked()
+    }
+}
+
+/// The size is always queried from the file, and never cached.
+impl Size for CachedMetadata {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.file.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+
+    #[test]
+    fn metadata() {
+        let path = TempPath::new("cached_metadata");
+        let file = CachedMetadata::new(RandomAccessFile::from(path.create_rw()));
+        file.write_all_at(b"Hello", 0).unwrap();
+
+        assert_eq!(file.metadata().unwrap().len(), 5);
+        file.get_ref().set_len(10).unwrap();
+        assert_eq!(file.metadata().unwrap().len(), 5);
+        assert_eq!(file.size().unwrap(), 10);
+        assert_eq!(file.refresh_metadata().unwrap().len(), 10);
+        assert_eq!(file.metadata().unwrap().len(), 10);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::lru::Lru;
+use std::{
+    fmt,
+    hash::Hash,
+    io,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A reader for a content-addressed store, where blobs are found by hash.
+///
+/// Blobs are resolved by a user-provided function, which may for example
+/// open a file named after the hash in a directory, and are then read with
+/// positional I/O through the returned [`Arc`], which implements
+/// [`ReadAt`](crate::ReadAt) and [`Size`](crate::Size) like the blob.
+///
+/// Resolved blobs are kept in a least-recently-used cache: when it is full,
+/// resolving a new blob drops the blob that was opened the longest time ago.
+/// Blobs still in use elsewhere are only dropped once released.
+pub struct CasReader<H, R, F> {
+    resolve: F,
+    blobs: Mutex<Lru<H, Arc<R>>>,
+}
+
+impl<H, R, F> CasReader<H, R, F>
+where
+    H: Hash + Eq + Clone,
+    F: Fn(&H) -> Option<R>,
+{
+    /// Creates a new `CasReader` caching up to `capacity` resolved blobs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(resolve: F, capacity: usize) -> Self {
+        Self {
+            resolve,
+            blobs: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Returns the blob with the given hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if the hash does
+    /// not resolve to a blob.
+    pub fn open(&self, hash: &H) -> io::Result<Arc<R>> {
+        let mut blobs = self.blobs.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(blob) = blobs.get(hash) {
+            return Ok(blob.clone());
+        }
+
+        let blob = (self.resolve)(hash)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no blob with this hash"))?;
+        let blob = Arc::new(blob);
+        blobs.insert(hash.clone(), blob.clone());
+        Ok(blob)
+    }
+
+    /// Returns the number of blobs currently cached.
+    #[must_use]
+    pub fn cached_blobs(&self) -> usize {
+        self.blobs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+impl<H, R, F> fmt::Debug for CasReader<H, R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CasReader").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReadAt, Size};
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[test]
+    fn read_blobs() {
+        let store: HashMap<_, _> = [
+            ([1u8; 4], b"Hello World!".to_vec()),
+            ([2u8; 4], b"Rust".to_vec()),
+        ]
+        .into_iter()
+        .collect();
+        let resolved = AtomicUsize::new(0);
+        let cas = CasReader::new(
+            |hash: &[u8; 4]| {
+                resolved.fetch_add(1, Ordering::SeqCst);
+                store.get(hash).cloned()
+            },
+            1,
+        );
+
+        let mut buf = [0; 5];
+        let hello = cas.open(&[1; 4]).unwrap();
+        assert_eq!(hello.size().unwrap(), 12);
+        hello.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+
+        let rust = cas.open(&[2; 4]).unwrap();
+        rust.read_exact_at(&mut buf[..3], 1).unwrap();
+        assert_eq!(&buf[..3], b"ust");
+
+        cas.open(&[2; 4]).unwrap();
+        assert_eq!(resolved.load(Ordering::SeqCst), 2);
+        assert_eq!(cas.cached_blobs(), 1);
+
+        let err = cas.open(&[3; 4]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{crc32::crc32, read_full, ReadAt, Size, WriteAt};
+use std::{fmt, io};
+
+const FOOTER_SIZE: u64 = 4;
+
+/// A layer that stores a checksum after each fixed-size block.
+///
+/// Data is split in blocks of `block_size` bytes, and each block is stored in
+/// the inner source followed by a 4-byte little-endian checksum of its
+/// contents, so the logical offset `o` is stored at the physical offset
+/// `o / block_size * (block_size + 4) + o % block_size`. Reads verify the
+/// checksum of every block they touch and fail with
+/// [`io::ErrorKind::InvalidData`] on mismatch, which detects torn or
+/// corrupted writes. The checksum is CRC-32 by default.
+///
+/// Writes must be aligned to whole blocks: their offset and length must be
+/// multiples of the block size, or they fail with
+/// [`io::ErrorKind::InvalidInput`]. Blocks that were never written, such as
+/// holes left by writing past the end, fail verification.
+///
+/// The size of the source is the size of its complete physical blocks,
+/// without their checksums.
+pub struct ChecksummedBlocks<W> {
+    inner: W,
+    block_size: u64,
+    checksum: fn(&[u8]) -> u32,
+}
+
+impl<W> ChecksummedBlocks<W> {
+    /// Creates a new `ChecksummedBlocks` layer with blocks of `block_size`
+    /// bytes, checksummed with CRC-32.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    #[inline]
+    pub fn new(inner: W, block_size: u64) -> Self {
+        Self::with_checksum(inner, block_size, crc32)
+    }
+
+    /// Creates a new `ChecksummedBlocks` layer with blocks of `block_size`
+    /// bytes, checksummed with the given function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn with_checksum(inner: W, block_size: u64, checksum: fn(&[u8]) -> u32) -> Self {
+        assert!(block_size != 0, "block size must be non-zero");
+
+        Self {
+            inner,
+            block_size,
+            checksum,
+        }
+    }
+
+    /// Returns the size of blocks, without their checksum.
+    #[must_use]
+    #[inline]
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[inline]
+    fn physical_block_size(&self) -> u64 {
+        self.block_size + FOOTER_SIZE
+    }
+
+    /// Returns the physical offset of a block.
+    fn block_offset(&self, index: u64) -> io::Result<u64> {
+        index
+            .checked_mul(self.physical_block_size())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))
+    }
+}
+
+impl<W: ReadAt> ReadAt for ChecksummedBlocks<W> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let bs = self.block_size;
+        let mut block = vec![0; self.physical_block_size() as usize];
+        let mut read = 0;
+
+        while read < buf.len() {
+            let pos = offset + read as u64;
+            let index = pos / bs;
+            let len = read_full(&self.inner, &mut block, self.block_offset(index)?)?;
+            if len == 0 {
+                break;
+            }
+            if len < block.len() {
+                return Err(corrupted_block());
+            }
+
+            let (data, footer) = block.split_at(bs as usize);
+            if (self.checksum)(data) != u32::from_le_bytes(footer.try_into().unwrap()) {
+                return Err(corrupted_block());
+            }
+
+            let start = (pos % bs) as usize;
+            let n = (data.len() - start).min(buf.len() - read);
+            buf[read..read + n].copy_from_slice(&data[start..start + n]);
+            read += n;
+        }
+
+        Ok(read)
+    }
+}
+
+impl<W: WriteAt> WriteAt for ChecksummedBlocks<W> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let bs = self.block_size;
+        if offset % bs != 0 || buf.len() as u64 % bs != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "write is not aligned to the block size",
+            ));
+        }
+
+        let physical_offset = self.block_offset(offset / bs)?;
+        let mut physical =
+            Vec::with_capacity(buf.len() / bs as usize * self.physical_block_size() as usize);
+        for data in buf.chunks(bs as usize) {
+            physical.extend_from_slice(data);
+            physical.extend_from_slice(&(self.checksum)(data).to_le_bytes());
+        }
+        self.inner.write_all_at(&physical, physical_offset)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let durable = self.inner.flush_tracked()?;
+        Ok(durable / self.physical_block_size() * self.block_size)
+    }
+}
+
+impl<W: Size> Size for ChecksummedBlocks<W> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        let size = self.inner.size()?;
+        Ok(size / self.physical_block_size() * self.block_size)
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for ChecksummedBlocks<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChecksummedBlocks")
+            .field("inner", &self.inner)
+            .field("block_size", &self.block_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cold]
+fn corrupted_block() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "block checksum mismatch")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn round_trip() {
+        let blocks = ChecksummedBlocks::new(MemFile::default(), 8);
+        blocks.write_all_at(b"Hello World!....", 0).unwrap();
+        blocks.write_all_at(b"Rust....", 16).unwrap();
+        assert_eq!(blocks.get_ref().contents().len(), 36);
+        assert_eq!(blocks.size().unwrap(), 24);
+        assert_eq!(blocks.flush_tracked().unwrap(), 24);
+
+        let mut buf = [0; 20];
+        assert_eq!(blocks.read_at(&mut buf, 6).unwrap(), 18);
+        assert_eq!(&buf[..18], b"World!....Rust....");
+
+        let err = blocks.write_all_at(b"Hi", 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn corrupted_block() {
+        let blocks = ChecksummedBlocks::new(MemFile::default(), 8);
+        blocks.write_all_at(b"Hello World!....", 0).unwrap();
+        blocks.get_ref().write_all_at(b"w", 18).unwrap();
+
+        let mut buf = [0; 8];
+        blocks.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello Wo");
+        let err = blocks.read_exact_at(&mut buf, 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,436 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    io::{self, Read, Write},
+    sync::{Mutex, PoisonError},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+const MAGIC: &[u8; 8] = b"sfdeflt1";
+const ENTRY_LEN: usize = 32;
+const FOOTER_LEN: usize = 32;
+/// The maximum block size, which bounds the memory used to decompress a block.
+const MAX_BLOCK_SIZE: usize = 64 << 20;
+
+/// The location of a compressed block.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    offset: u64,
+    compressed_len: u64,
+    len: u64,
+}
+
+/// A writer that transparently compresses fixed-size blocks with deflate.
+///
+/// Each block is compressed separately and appended to the inner writer, so
+/// that [`Decompressed`] can read any of them without decompressing the others.
+///
+/// Writes must be aligned on the block size, both in offset and length, or
+/// they fail with [`io::ErrorKind::InvalidInput`], since rewriting part of a
+/// block would lose the rest of it. The only exception is a final partial
+/// block that ends at or past the current end of the data: it is stored
+/// partially, and the rest of the block reads as zeros. Rewriting a block
+/// appends a new version of it.
+///
+/// # Format
+///
+/// The index of blocks is kept in memory and written after the last block on
+/// [`flush`](WriteAt::flush). It is a sequence of entries, one for each block
+/// in logical order, made of four little-endian `u64`: the logical block
+/// number, the physical offset of the compressed block, its compressed length
+/// and its decompressed length. It is followed by a footer of four `u64`: the
+/// offset of the index, the number of entries, the block size, and the magic
+/// bytes `sfdeflt1`.
+#[derive(Debug)]
+pub struct CompressedWriter<W> {
+    inner: W,
+    block_size: usize,
+    level: Compression,
+    state: Mutex<WriterState>,
+}
+
+#[derive(Debug, Default)]
+struct WriterState {
+    blocks: BTreeMap<u64, Entry>,
+    end: u64,
+}
+
+impl<W> CompressedWriter<W> {
+    /// Creates a new `CompressedWriter` with the default compression level.
+    ///
+    /// The inner writer is expected to be empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero or greater than 64 MiB.
+    pub fn new(inner: W, block_size: usize) -> Self {
+        Self::with_level(inner, block_size, Compression::default())
+    }
+
+    /// Creates a new `CompressedWriter` with the given compression level.
+    ///
+    /// The inner writer is expected to be empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero or greater than 64 MiB.
+    pub fn with_level(inner: W, block_size: usize, level: Compression) -> Self {
+        assert!(
+            block_size != 0 && block_size <= MAX_BLOCK_SIZE,
+            "block size must be non-zero and at most 64 MiB"
+        );
+
+        Self {
+            inner,
+            block_size,
+            level,
+            state: Mutex::new(WriterState::default()),
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    ///
+    /// The index is not written, so [`flush`](WriteAt::flush) should be
+    /// called first.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: WriteAt> WriteAt for CompressedWriter<W> {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let block_size = self.block_size as u64;
+        if offset % block_size != 0 {
+            return Err(unaligned_write());
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if buf.len() % self.block_size != 0 {
+            let end = offset.checked_add(buf.len() as u64);
+            let logical_end = state
+                .blocks
+                .iter()
+                .next_back()
+                .map_or(0, |(&block, entry)| block * block_size + entry.len);
+            if end.map_or(true, |end| end < logical_end) {
+                return Err(unaligned_write());
+            }
+        }
+
+        for (i, block) in buf.chunks(self.block_size).enumerate() {
+            let mut encoder = DeflateEncoder::new(Vec::new(), self.level);
+            encoder.write_all(block)?;
+            let compressed = encoder.finish()?;
+
+            let entry = Entry {
+                offset: state.end,
+                compressed_len: compressed.len() as u64,
+                len: block.len() as u64,
+            };
+            self.inner.write_all_at(&compressed, entry.offset)?;
+            state.end += entry.compressed_len;
+            state.blocks.insert(offset / block_size + i as u64, entry);
+        }
+
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.write_at(buf, offset)?;
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut index = Vec::with_capacity(state.blocks.len() * ENTRY_LEN + FOOTER_LEN);
+        for (&block, entry) in &state.blocks {
+            index.extend_from_slice(&block.to_le_bytes());
+            index.extend_from_slice(&entry.offset.to_le_bytes());
+            index.extend_from_slice(&entry.compressed_len.to_le_bytes());
+            index.extend_from_slice(&entry.len.to_le_bytes());
+        }
+        index.extend_from_slice(&state.end.to_le_bytes());
+        index.extend_from_slice(&(state.blocks.len() as u64).to_le_bytes());
+        index.extend_from_slice(&(self.block_size as u64).to_le_bytes());
+        index.extend_from_slice(MAGIC);
+
+        // The index is overwritten by the next blocks, and written again after
+        // them on the next flush.
+        self.inner.write_all_at(&index, state.end)?;
+        self.inner.flush()
+    }
+}
+
+/// A reader that decompresses data written by a [`CompressedWriter`].
+///
+/// Only the blocks covering a read are decompressed, and the last decompressed
+/// block is cached. Blocks that were never written read as zeros.
+#[derive(Debug)]
+pub struct Decompressed<R> {
+    inner: R,
+    block_size: u64,
+    blocks: BTreeMap<u64, Entry>,
+    size: u64,
+    cache: Mutex<Option<(u64, Vec<u8>)>>,
+}
+
+impl<R> Decompressed<R>
+where
+    R: ReadAt + Size,
+{
+    /// Opens compressed data, reading its index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the data
+    /// does not end with a valid index, or if the index refers to blocks
+    /// outside of the data.
+    pub fn new(inner: R) -> io::Result<Self> {
+        let size = inner.size()?;
+        let footer_offset = size
+            .checked_sub(FOOTER_LEN as u64)
+            .ok_or_else(invalid_index)?;
+        let mut footer = [0; FOOTER_LEN];
+        inner.read_exact_at(&mut footer, footer_offset)?;
+        if &footer[24..] != MAGIC {
+            return Err(invalid_index());
+        }
+
+        let index_offset = read_u64(&footer[0..]);
+        let count = read_u64(&footer[8..]);
+        let block_size = read_u64(&footer[16..]);
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE as u64 {
+            return Err(invalid_index());
+        }
+        let index_len = count
+            .checked_mul(ENTRY_LEN as u64)
+            .filter(|&len| index_offset.checked_add(len) == Some(footer_offset))
+            .ok_or_else(invalid_index)?;
+
+        let mut index = vec![0; index_len as usize];
+        inner.read_exact_at(&mut index, index_offset)?;
+
+        let mut blocks = BTreeMap::new();
+        let mut size = 0;
+        for entry in index.chunks(ENTRY_LEN) {
+            let block = read_u64(&entry[0..]);
+            let entry = Entry {
+                offset: read_u64(&entry[8..]),
+                compressed_len: read_u64(&entry[16..]),
+                len: read_u64(&entry[24..]),
+            };
+            let compressed_end = entry.offset.checked_add(entry.compressed_len);
+            if entry.len > block_size || compressed_end.map_or(true, |end| end > index_offset) {
+                return Err(invalid_index());
+            }
+            size = block
+                .checked_mul(block_size)
+                .and_then(|start| start.checked_add(entry.len))
+                .ok_or_else(invalid_index)?
+                .max(size);
+            blocks.insert(block, entry);
+        }
+
+        Ok(Self {
+            inner,
+            block_size,
+            blocks,
+            size,
+            cache: Mutex::new(None),
+        })
+    }
+}
+
+impl<R> Decompressed<R> {
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ReadAt> ReadAt for Decompressed<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+
+        let block = offset / self.block_size;
+        let in_block = (offset % self.block_size) as usize;
+        let entry = match self.blocks.get(&block) {
+            Some(entry) => entry,
+            None => {
+                let len = buf.len().min((self.block_size as usize) - in_block);
+                buf[..len].fill(0);
+                return Ok(len);
+            }
+        };
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let data = match &mut *cache {
+            Some((cached, data)) if *cached == block => data,
+            cache => {
+                let mut compressed = vec![0; entry.compressed_len as usize];
+                self.inner.read_exact_at(&mut compressed, entry.offset)?;
+
+                // Decompressing one more byte than expected is enough to
+                // detect a block that is too long, without inflating it all.
+                let mut data = Vec::with_capacity(entry.len as usize);
+                DeflateDecoder::new(&compressed[..])
+                    .take(entry.len + 1)
+                    .read_to_end(&mut data)?;
+                if data.len() as u64 != entry.len {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid compressed block length",
+                    ));
+                }
+
+                &mut cache.insert((block, data)).1
+            }
+        };
+
+        match data.get(in_block..) {
+            Some(data) if !data.is_empty() => data.read_at(buf, 0),
+            // Partial blocks are followed by zeros.
+            _ => {
+                let len = buf.len().min((self.block_size as usize) - in_block);
+                buf[..len].fill(0);
+                Ok(len)
+            }
+        }
+    }
+}
+
+impl<R> Size for Decompressed<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.size)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.size)
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+#[cold]
+fn unaligned_write() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "write is not aligned on the block size",
+    )
+}
+
+#[cold]
+fn invalid_index() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid compressed index")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..100u8).flat_map(|i| [i; 10]).collect();
+
+        let writer = CompressedWriter::new(MemFile::default(), 64);
+        writer.write_all_at(&data[..640], 0).unwrap();
+        writer.write_all_at(&data[640..], 640).unwrap();
+        writer.flush().unwrap();
+        assert!(writer.get_ref().size().unwrap() < data.len() as u64);
+
+        let reader = Decompressed::new(writer.into_inner()).unwrap();
+        assert_eq!(reader.size().unwrap(), 1000);
+
+        let mut buf = [0; 100];
+        reader.read_exact_at(&mut buf, 500).unwrap();
+        assert_eq!(buf, data[500..600]);
+        reader.read_exact_at(&mut buf, 900).unwrap();
+        assert_eq!(buf, data[900..]);
+        assert_eq!(reader.read_at(&mut buf, 1000).unwrap(), 0);
+    }
+
+    #[test]
+    fn unaligned_writes_fail() {
+        let writer = CompressedWriter::new(MemFile::default(), 64);
+
+        let err = writer.write_at(&[0; 64], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // A partial block would lose the end of the block it replaces.
+        writer.write_all_at(&[1; 128], 0).unwrap();
+        let err = writer.write_at(&[2; 3], 64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = writer.write_at(&[2; 3], 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // A partial block is allowed at the end of the data.
+        writer.write_all_at(&[2; 130], 0).unwrap();
+        writer.write_all_at(&[3; 3], 128).unwrap();
+    }
+
+    #[test]
+    fn partial_blocks() {
+        let writer = CompressedWriter::new(MemFile::default(), 64);
+        writer.write_all_at(&[1; 100], 0).unwrap();
+        writer.write_all_at(&[2; 10], 192).unwrap();
+        writer.flush().unwrap();
+
+        let reader = Decompressed::new(writer.into_inner()).unwrap();
+        assert_eq!(reader.size().unwrap(), 202);
+
+        let mut buf = [0xff; 202];
+        reader.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(buf[..100], [1; 100]);
+        assert_eq!(buf[100..192], [0; 92]);
+        assert_eq!(buf[192..], [2; 10]);
+    }
+
+    #[test]
+    fn corrupted_index() {
+        let writer = CompressedWriter::new(MemFile::default(), 64);
+        writer.write_all_at(&[1; 64], 0).unwrap();
+        writer.flush().unwrap();
+        let file = writer.into_inner();
+        let index_offset = file.size().unwrap() - (ENTRY_LEN + FOOTER_LEN) as u64;
+
+        // A block that overlaps the index.
+        let entry = file.contents()[index_offset as usize..][..ENTRY_LEN].to_vec();
+        file.write_all_at(&index_offset.to_le_bytes(), index_offset + 8)
+            .unwrap();
+        let err = Decompressed::new(&file).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A block that decompresses to more than its length.
+        file.write_all_at(&entry, index_offset).unwrap();
+        file.write_all_at(&10u64.to_le_bytes(), index_offset + 24)
+            .unwrap();
+        let reader = Decompressed::new(&file).unwrap();
+        let err = reader.read_exact_at(&mut [0; 10], 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{ReadAt, WriteAt};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
+    hash::Hasher,
+    io,
+    sync::{Mutex, PoisonError},
+};
+
+/// A writer that stores identical blocks only once.
+///
+/// Data written to this type is split in blocks of a fixed size. Each block is
+/// hashed, and if a block with the same content was already stored in the
+/// inner writer, it is not written again. New blocks are appended to the inner
+/// writer, so logical offsets (used with `write_at` and `read_at`) and physical
+/// offsets (where the content lives in the inner writer) differ.
+///
+/// Writes must be aligned on the block size, both in offset and length, or
+/// they fail with [`io::ErrorKind::InvalidInput`]. Writes are serialized by an
+/// internal lock.
+///
+/// The index mapping logical blocks to physical offsets is kept in memory and
+/// persisted to `index` on [`flush`](WriteAt::flush), as a little-endian `u64`
+/// count followed by `(logical block, physical offset)` pairs of `u64`. A
+/// persisted writer is opened again with [`open`](Self::open).
+#[derive(Debug)]
+pub struct DedupWriter<W, I = io::Sink> {
+    inner: W,
+    index: I,
+    block_size: usize,
+    state: Mutex<State>,
+}
+
+/// The number of index entries read at once by [`DedupWriter::open`].
+const INDEX_CHUNK: usize = 4096;
+
+#[derive(Debug, Default)]
+struct State {
+    by_hash: HashMap<u64, Vec<u64>>,
+    blocks: BTreeMap<u64, u64>,
+    end: u64,
+}
+
+impl<W> DedupWriter<W> {
+    /// Creates a new `DedupWriter` that does not persist its index.
+    ///
+    /// The inner writer is expected to be empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn new(inner: W, block_size: usize) -> Self {
+        Self::with_index(inner, io::sink(), block_size)
+    }
+}
+
+impl<W, I> DedupWriter<W, I> {
+    /// Creates a new `DedupWriter` that persists its index to `index`.
+    ///
+    /// The inner writer is expected to be empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn with_index(inner: W, index: I, block_size: usize) -> Self {
+        assert!(block_size != 0, "block size must be non-zero");
+
+        Self {
+            inner,
+            index,
+            block_size,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Returns the physical offset where the byte at the given logical offset
+    /// is stored, if it was written.
+    #[must_use]
+    pub fn physical_offset(&self, logical_offset: u64) -> Option<u64> {
+        let block_size = self.block_size as u64;
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let start = state.blocks.get(&(logical_offset / block_size))?;
+        Some(start + logical_offset % block_size)
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer and index.
+    #[inline]
+    pub fn into_inner(self) -> (W, I) {
+        (self.inner, self.index)
+    }
+}
+
+impl<W, I> DedupWriter<W, I>
+where
+    W: ReadAt,
+    I: ReadAt,
+{
+    /// Opens a `DedupWriter` from an inner writer and the index it persisted.
+    ///
+    /// The index is read back, and the blocks it references are hashed again
+    /// so that new writes are deduplicated against them. Blocks written after
+    /// the last [`flush`](WriteAt::flush) are lost, and their space in the
+    /// inner writer is reused.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the index is malformed,
+    /// and with [`io::ErrorKind::UnexpectedEof`] if it is truncated or if a
+    /// block is missing from the inner writer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn open(inner: W, index: I, block_size: usize) -> io::Result<Self> {
+        let this = Self::with_index(inner, index, block_size);
+        let count = u64::from_le_bytes(this.index.read_array_at(0)?);
+
+        let mut state = State::default();
+        let mut hashed = HashSet::new();
+        let mut block = vec![0; block_size];
+        let mut entries = vec![0; 16 * INDEX_CHUNK];
+        let mut read = 0;
+        while read < count {
+            let n = (count - read).min(INDEX_CHUNK as u64) as usize;
+            let entries = &mut entries[..16 * n];
+            this.index.read_exact_at(entries, 8 + 16 * read)?;
+            read += n as u64;
+
+            for entry in entries.chunks_exact(16) {
+                let logical = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                let start = u64::from_le_bytes(entry[8..].try_into().unwrap());
+                let end = start.checked_add(block_size as u64);
+                match end {
+                    Some(end) if start % block_size as u64 == 0 => {
+                        state.end = state.end.max(end);
+                    }
+                    _ => return Err(invalid_index()),
+                }
+
+                state.blocks.insert(logical, start);
+                // Blocks stored once may be referenced several times.
+                if hashed.insert(start) {
+                    this.inner.read_exact_at(&mut block, start)?;
+                    let hash = hash_block(&block);
+                    state.by_hash.entry(hash).or_default().push(start);
+                }
+            }
+        }
+
+        *this.state.lock().unwrap_or_else(PoisonError::into_inner) = state;
+        Ok(this)
+    }
+}
+
+impl<W, I> DedupWriter<W, I>
+where
+    W: ReadAt + WriteAt,
+{
+    /// Stores a block and returns its physical offset.
+    fn store_block(&self, state: &mut State, block: &[u8]) -> io::Result<u64> {
+        let hash = hash_block(block);
+
+        // Hashes may collide, so compare the stored content to be sure.
+        let mut stored = vec![0; block.len()];
+        for &offset in state.by_hash.get(&hash).into_iter().flatten() {
+            self.inner.read_exact_at(&mut stored, offset)?;
+            if stored == block {
+                return Ok(offset);
+            }
+        }
+
+        let offset = state.end;
+        self.inner.write_all_at(block, offset)?;
+        state.end += block.len() as u64;
+        state.by_hash.entry(hash).or_default().push(offset);
+        Ok(offset)
+    }
+}
+
+impl<W, I> ReadAt for DedupWriter<W, I>
+where
+    W: ReadAt,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let block_size = self.block_size as u64;
+        let in_block = offset % block_size;
+        let len = buf.len().min((block_size - in_block) as usize);
+
+        let start = {
+            let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            match state.blocks.get(&(offset / block_size)) {
+                Some(&start) => start,
+                // Blocks that were never written read as zeros, unless they
+                // are past the last written one.
+                None => match state.blocks.keys().next_back() {
+                    Some(&last) if last > offset / block_size => {
+                        buf[..len].fill(0);
+                        return Ok(len);
+                    }
+                    _ => return Ok(0),
+                },
+            }
+        };
+
+        self.inner.read_at(&mut buf[..len], start + in_block)
+    }
+}
+
+impl<W, I> WriteAt for DedupWriter<W, I>
+where
+    W: ReadAt + WriteAt,
+    I: WriteAt,
+{
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let block_size = self.block_size as u64;
+        if offset % block_size != 0 || buf.len() % self.block_size != 0 {
+            return Err(unaligned_write());
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let first_block = offset / block_size;
+        for (i, block) in buf.chunks(self.block_size).enumerate() {
+            let start = self.store_block(&mut state, block)?;
+            state.blocks.insert(first_block + i as u64, start);
+        }
+
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.write_at(buf, offset)?;
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()?;
+
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut index = Vec::with_capacity(8 + 16 * state.blocks.len());
+        index.extend_from_slice(&(state.blocks.len() as u64).to_le_bytes());
+        for (&block, &start) in &state.blocks {
+            index.extend_from_slice(&block.to_le_bytes());
+            index.extend_from_slice(&start.to_le_bytes());
+        }
+        drop(state);
+
+        self.index.write_all_at(&index, 0)?;
+        self.index.flush()
+    }
+}
+
+fn hash_block(block: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(block);
+    hasher.finish()
+}
+
+#[cold]
+fn invalid_index() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid dedup index")
+}
+
+#[cold]
+fn unaligned_write() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "write is not aligned on the block size",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingWriter {
+        file: MemFile,
+        writes: AtomicUsize,
+    }
+
+    impl ReadAt for CountingWriter {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            self.file.read_at(buf, offset)
+        }
+    }
+
+    impl WriteAt for CountingWriter {
+        fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            self.file.write_at(buf, offset)
+        }
+    }
+
+    #[test]
+    fn identical_blocks_are_stored_once() {
+        let index = MemFile::default();
+        let writer = DedupWriter::with_index(CountingWriter::default(), &index, 4);
+
+        writer.write_all_at(b"abcd", 0).unwrap();
+        writer.write_all_at(b"abcd", 8).unwrap();
+        writer.write_all_at(b"efgh", 4).unwrap();
+
+        assert_eq!(writer.get_ref().writes.load(Ordering::Relaxed), 2);
+        assert_eq!(writer.get_ref().file.contents(), b"abcdefgh");
+        assert_eq!(writer.physical_offset(9), Some(1));
+        assert_eq!(writer.physical_offset(12), None);
+
+        let mut buf = [0; 12];
+        writer.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"abcdefghabcd");
+
+        writer.flush().unwrap();
+        let index = index.contents();
+        assert_eq!(index.len(), 8 + 3 * 16);
+        assert_eq!(index[..8], 3u64.to_le_bytes());
+    }
+
+    #[test]
+    fn reopen() {
+        let index = MemFile::default();
+        let writer = DedupWriter::with_index(CountingWriter::default(), &index, 4);
+        writer.write_all_at(b"abcdefghabcd", 0).unwrap();
+        writer.flush().unwrap();
+        let (inner, _) = writer.into_inner();
+
+        let writer = DedupWriter::open(inner, &index, 4).unwrap();
+        let mut buf = [0; 12];
+        writer.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"abcdefghabcd");
+        assert_eq!(writer.physical_offset(9), Some(1));
+
+        // Known blocks are deduplicated, and new ones are appended.
+        writer.write_all_at(b"efghijkl", 12).unwrap();
+        assert_eq!(writer.get_ref().writes.load(Ordering::Relaxed), 3);
+        assert_eq!(writer.get_ref().file.contents(), b"abcdefghijkl");
+        assert_eq!(writer.physical_offset(12), Some(4));
+        assert_eq!(writer.physical_offset(16), Some(8));
+
+        index.write_all_at(&1u64.to_le_bytes(), 0).unwrap();
+        index.write_all_at(&3u64.to_le_bytes(), 16).unwrap();
+        let err = DedupWriter::open(MemFile::default(), &index, 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn unaligned_writes_fail() {
+        let writer = DedupWriter::new(CountingWriter::default(), 4);
+
+        let err = writer.write_at(b"abcd", 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = writer.write_at(b"abc", 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{ReadAt, Size};
+use std::{
+    io,
+    ops::Range,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A reader that injects errors and short reads, to test error handling.
+///
+/// Faults are configured with rules, which are evaluated on each call to
+/// [`read_at`](ReadAt::read_at) in the order in which they were added: the
+/// first rule that applies decides the fault. Calls to which no rule applies
+/// are forwarded to the inner reader.
+///
+/// Faults are deterministic: they only depend on the offset of the read and
+/// on the number of reads made before it, which is counted atomically.
+#[derive(Debug)]
+pub struct FaultInjecting<R> {
+    inner: R,
+    rules: Vec<Rule>,
+    reads: AtomicU64,
+}
+
+#[derive(Debug)]
+enum Rule {
+    /// Fails the nth read.
+    FailRead(u64, io::ErrorKind),
+    /// Fails reads overlapping a range.
+    FailRange(Range<u64>, io::ErrorKind),
+    /// Shortens reads starting in a range.
+    ShortRead(Range<u64>, usize),
+}
+
+impl<R> FaultInjecting<R> {
+    /// Creates a new `FaultInjecting` without rules, which forwards all reads
+    /// to `inner`.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            rules: Vec::new(),
+            reads: AtomicU64::new(0),
+        }
+    }
+
+    /// Fails the `n`th read, counting from 1, with an error of the given
+    /// kind.
+    ///
+    /// Only this read fails: the next ones are forwarded again.
+    pub fn fail_nth_read(&mut self, n: u64, kind: io::ErrorKind) -> &mut Self {
+        self.rules.push(Rule::FailRead(n, kind));
+        self
+    }
+
+    /// Fails all reads overlapping `range` with an error of the given kind.
+    pub fn fail_range(&mut self, range: Range<u64>, kind: io::ErrorKind) -> &mut Self {
+        self.rules.push(Rule::FailRange(range, kind));
+        self
+    }
+
+    /// Reads at most `max_len` bytes for all reads starting in `range`.
+    ///
+    /// Reads are shortened even if more data is available.
+    pub fn short_read_range(&mut self, range: Range<u64>, max_len: usize) -> &mut Self {
+        self.rules.push(Rule::ShortRead(range, max_len));
+        self
+    }
+
+    /// Returns the number of reads made so far, including failed ones.
+    #[must_use]
+    #[inline]
+    pub fn reads(&self) -> u64 {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ReadAt> ReadAt for FaultInjecting<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let n = self.reads.fetch_add(1, Ordering::Relaxed) + 1;
+        let end = offset.saturating_add(buf.len() as u64);
+
+        for rule in &self.rules {
+            match *rule {
+                Rule::FailRead(nth, kind) if nth == n => {
+                    return Err(injected_error(kind));
+                }
+                Rule::FailRange(ref range, kind) if offset < range.end && range.start < end => {
+                    return Err(injected_error(kind));
+                }
+                Rule::ShortRead(ref range, max_len) if range.contains(&offset) => {
+                    let len = buf.len().min(max_len);
+                    return self.inner.read_at(&mut buf[..len], offset);
+                }
+                _ => (),
+            }
+        }
+
+        self.inner.read_at(buf, offset)
+    }
+}
+
+impl<R: Size> Size for FaultInjecting<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cold]
+fn injected_error(kind: io::ErrorKind) -> io::Error {
+    io::Error::new(kind, "injected fault")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_shot_error() {
+        let mut reader = FaultInjecting::new(&b"Hello World!"[..]);
+        reader.fail_nth_read(2, io::ErrorKind::TimedOut);
+
+        let mut buf = [0; 5];
+        reader.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello");
+
+        let err = reader.read_exact_at(&mut buf, 6).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+        assert_eq!(reader.reads(), 3);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut reader = FaultInjecting::new(&b"Hello World!"[..]);
+        reader
+            .short_read_range(0..4, 1)
+            .fail_range(10..11, io::ErrorKind::Other);
+
+        let mut buf = [0; 5];
+        assert_eq!(reader.read_at(&mut buf, 2).unwrap(), 1);
+        reader.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello");
+        assert_eq!(reader.reads(), 6);
+
+        assert!(reader.read_exact_at(&mut buf, 6).is_err());
+        reader.read_exact_at(&mut buf[..4], 6).unwrap();
+        assert_eq!(&buf[..4], b"Worl");
+    }
+}
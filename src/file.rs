@@ -16,9 +16,9 @@ use std::os::wasi::prelude::*;
 #[cfg(target_os = "windows")]
 use std::os::windows::prelude::*;
 
-use crate::Adapter;
+use crate::{Adapter, ReadOnly, ReadaheadFile};
 
-use super::{ReadAt, WriteAt};
+use super::{ReadAt, Size, WriteAt};
 
 #[cfg(any(target_os = "wasi", target_os = "wasip1"))]
 trait FileExt {
@@ -42,8 +42,7 @@ impl FileExt for File {
                 buf_len: buffer.len(),
             }];
 
-            wasi::fd_pread(raw, &iovec, offset)
-                .map_err(|err| io::Error::from_raw_os_error(err.raw() as _))
+            wasi::fd_pread(raw, &iovec, offset).map_err(errno_to_error)
         }
     }
 
@@ -52,8 +51,7 @@ impl FileExt for File {
             let raw = self.as_raw_fd() as wasi::Fd;
             let iovec = std::mem::transmute(bufs);
 
-            wasi::fd_pread(raw, iovec, offset)
-                .map_err(|err| io::Error::from_raw_os_error(err.raw() as _))
+            wasi::fd_pread(raw, iovec, offset).map_err(errno_to_error)
         }
     }
 
@@ -66,8 +64,7 @@ impl FileExt for File {
                 buf_len: buffer.len(),
             }];
 
-            wasi::fd_pwrite(raw, &iovec, offset)
-                .map_err(|err| io::Error::from_raw_os_error(err.raw() as _))
+            wasi::fd_pwrite(raw, &iovec, offset).map_err(errno_to_error)
         }
     }
 
@@ -76,12 +73,70 @@ impl FileExt for File {
             let raw = self.as_raw_fd() as wasi::Fd;
             let iovec = std::mem::transmute(bufs);
 
-            wasi::fd_pwrite(raw, iovec, offset)
-                .map_err(|err| io::Error::from_raw_os_error(err.raw() as _))
+            wasi::fd_pwrite(raw, iovec, offset).map_err(errno_to_error)
         }
     }
 }
 
+/// Raw values of the WASI errnos mapped by [`errno_kind`].
+///
+/// They are fixed by the WASI specification, and duplicated here so that the
+/// mapping can be tested on every platform.
+#[cfg(any(test, target_os = "wasi", target_os = "wasip1"))]
+mod errno {
+    pub const ACCES: u16 = 2;
+    pub const AGAIN: u16 = 6;
+    pub const BADF: u16 = 8;
+    pub const EXIST: u16 = 20;
+    pub const INTR: u16 = 27;
+    pub const INVAL: u16 = 28;
+    pub const NOENT: u16 = 44;
+    pub const NOMEM: u16 = 48;
+    pub const NOSYS: u16 = 52;
+    pub const NOTSUP: u16 = 58;
+    pub const PERM: u16 = 63;
+    pub const PIPE: u16 = 64;
+    pub const TIMEDOUT: u16 = 73;
+    pub const NOTCAPABLE: u16 = 76;
+}
+
+/// Maps the most common WASI errnos to a portable [`io::ErrorKind`].
+#[cfg(any(test, target_os = "wasi", target_os = "wasip1"))]
+fn errno_kind(raw: u16) -> Option<io::ErrorKind> {
+    let kind = match raw {
+        errno::NOENT => io::ErrorKind::NotFound,
+        errno::ACCES | errno::PERM | errno::NOTCAPABLE => io::ErrorKind::PermissionDenied,
+        errno::BADF | errno::INVAL => io::ErrorKind::InvalidInput,
+        errno::EXIST => io::ErrorKind::AlreadyExists,
+        errno::INTR => io::ErrorKind::Interrupted,
+        errno::AGAIN => io::ErrorKind::WouldBlock,
+        errno::PIPE => io::ErrorKind::BrokenPipe,
+        errno::TIMEDOUT => io::ErrorKind::TimedOut,
+        errno::NOMEM => io::ErrorKind::OutOfMemory,
+        errno::NOSYS | errno::NOTSUP => io::ErrorKind::Unsupported,
+        // `ErrorKind::StorageFull` is not available with our MSRV, so `NOSPC`
+        // is left to the standard library, which decodes it on recent
+        // toolchains.
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Converts a WASI errno to an error that keeps its raw OS error code.
+///
+/// The standard library decodes the kind of the error from its code, and
+/// only errnos it does not decode to the expected kind are wrapped to fix
+/// their kind.
+#[cfg(any(target_os = "wasi", target_os = "wasip1"))]
+#[cold]
+fn errno_to_error(err: wasi::Errno) -> io::Error {
+    let error = io::Error::from_raw_os_error(err.raw() as _);
+    match errno_kind(err.raw()) {
+        Some(kind) if error.kind() != kind => io::Error::new(kind, error),
+        _ => error,
+    }
+}
+
 #[cfg(any(unix, target_os = "windows", target_os = "wasi"))]
 type FileRepr = File;
 
@@ -117,6 +172,30 @@ impl RandomAccessFile {
         Ok(RandomAccessFile::from(f))
     }
 
+    /// Creates a new `RandomAccessFile` from an open [`File`], checking that
+    /// it supports positional I/O.
+    ///
+    /// Unlike the `From<File>` implementation, this queries the metadata of
+    /// the file, and fails with [`io::ErrorKind::InvalidInput`] if it is not a
+    /// regular file or, on Unix, a block device. This catches pipes, sockets
+    /// and terminals at construction instead of at the first read.
+    pub fn from_file_checked(file: File) -> io::Result<RandomAccessFile> {
+        let file_type = file.metadata()?.file_type();
+
+        #[cfg(unix)]
+        let seekable = file_type.is_file() || file_type.is_block_device();
+        #[cfg(not(unix))]
+        let seekable = file_type.is_file();
+
+        if !seekable {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "file does not support positional I/O",
+            ));
+        }
+        Ok(RandomAccessFile::from(file))
+    }
+
     #[inline]
     pub(crate) fn with_file<T>(&self, f: impl FnOnce(&File) -> T) -> T {
         #[cfg(any(unix, target_os = "windows", target_os = "wasi"))]
@@ -147,6 +226,32 @@ impl RandomAccessFile {
         self.with_file(|f| f.sync_data())
     }
 
+    /// Synchronizes the directory containing `path`, so that the creation or
+    /// renaming of a file at this path is durable.
+    ///
+    /// [`sync_all`](Self::sync_all) makes the content of a file durable, but not
+    /// its directory entry. On Unix, this opens the parent directory and calls
+    /// `fsync` on it. On other platforms, this is a no-op, as directory entries
+    /// are not synchronized separately.
+    pub fn sync_parent_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            let path = path.as_ref();
+            let parent = match path.parent() {
+                Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+                Some(parent) => parent,
+                None => path,
+            };
+            File::open(parent)?.sync_all()
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            Ok(())
+        }
+    }
+
     /// Truncates or extends the underlying file, updating the size of this file
     /// to become `size`.
     ///
@@ -164,6 +269,108 @@ impl RandomAccessFile {
         self.with_file(|f| f.metadata())
     }
 
+    /// Returns the length of the file.
+    ///
+    /// This is equivalent to `metadata()?.len()`, but uses the most direct
+    /// system call: `fstat` on Unix and `GetFileSizeEx` on Windows.
+    pub fn len(&self) -> io::Result<u64> {
+        #[cfg(unix)]
+        {
+            #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+            use libc::{fstat, stat};
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            use libc::{fstat64 as fstat, stat64 as stat};
+
+            let mut st = std::mem::MaybeUninit::<stat>::uninit();
+            if unsafe { fstat(self.as_raw_fd(), st.as_mut_ptr()) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: `fstat` succeeded, so it initialized `st`.
+            let size = unsafe { st.assume_init() }.st_size;
+            Ok(size as u64)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use windows_sys::Win32::Storage::FileSystem::GetFileSizeEx;
+
+            let mut size = 0;
+            if unsafe { GetFileSizeEx(self.as_raw_handle() as _, &mut size) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(size as u64)
+        }
+
+        #[cfg(not(any(unix, target_os = "windows")))]
+        {
+            Ok(self.metadata()?.len())
+        }
+    }
+
+    /// Returns `true` if the file is empty.
+    ///
+    /// See [`len`](Self::len) for details.
+    #[inline]
+    pub fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Queries what kind of file this is, to choose an I/O strategy.
+    ///
+    /// See [`Capabilities`] for details.
+    pub fn capabilities(&self) -> io::Result<Capabilities> {
+        self.with_file(|f| {
+            let metadata = f.metadata()?;
+
+            #[cfg(unix)]
+            let (is_block_device, preferred_block_size) = (
+                metadata.file_type().is_block_device(),
+                Some(metadata.blksize()),
+            );
+            #[cfg(not(unix))]
+            let (is_block_device, preferred_block_size) = (false, None);
+
+            Ok(Capabilities {
+                is_regular: metadata.is_file(),
+                is_block_device,
+                is_seekable: io::Seek::stream_position(&mut &*f).is_ok(),
+                preferred_block_size,
+            })
+        })
+    }
+
+    /// Copies `len` bytes at `src_offset` in this file to `dst_offset` in
+    /// `dst`, and returns the number of bytes copied.
+    ///
+    /// On Linux, this uses `copy_file_range`, which keeps the data in the
+    /// kernel and may even share blocks between the files on some
+    /// filesystems. Elsewhere, or if the files cannot be copied this way (for
+    /// example if they are on different filesystems on older kernels), the
+    /// data is copied through a buffer. macOS's `fcopyfile` only copies whole
+    /// files, so it is not used.
+    ///
+    /// Fewer than `len` bytes are copied only if the end of this file is
+    /// reached. The result is unspecified if `dst` is this file and the
+    /// ranges overlap.
+    pub fn copy_range_to(
+        &self,
+        src_offset: u64,
+        dst: &RandomAccessFile,
+        dst_offset: u64,
+        len: u64,
+    ) -> io::Result<u64> {
+        #[cfg(target_os = "linux")]
+        {
+            match copy_file_range(self, src_offset, dst, dst_offset, len) {
+                Ok(Some(copied)) => return Ok(copied),
+                Ok(None) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        copy_range_buffered(self, src_offset, dst, dst_offset, len)
+    }
+
     /// Creates a new `File` instance that shares the same underlying file handle
     /// as the existing `File` instance
     ///
@@ -197,6 +404,116 @@ impl RandomAccessFile {
             self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
         }
     }
+
+    /// Closes the file, returning any error reported by the OS.
+    ///
+    /// Dropping a file ignores errors of closing it, but some filesystems,
+    /// such as NFS, only report write errors when the file is closed. Note
+    /// that a successful close does not mean that the data is durable: use
+    /// [`sync_all`](Self::sync_all) for this.
+    ///
+    /// On Unix, `EINTR` is not reported, as the descriptor is closed anyway
+    /// on most systems and cannot be closed again. On platforms without a
+    /// way to report errors of closing a file, this always succeeds.
+    pub fn close(self) -> io::Result<()> {
+        let file = self.into_inner();
+
+        #[cfg(unix)]
+        {
+            if unsafe { libc::close(file.into_raw_fd()) } == -1 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+            Ok(())
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use windows_sys::Win32::Foundation::CloseHandle;
+
+            if unsafe { CloseHandle(file.into_raw_handle() as _) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        #[cfg(any(target_os = "wasi", target_os = "wasip1"))]
+        {
+            unsafe { wasi::fd_close(file.into_raw_fd() as wasi::Fd) }.map_err(errno_to_error)
+        }
+
+        #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
+        {
+            drop(file);
+            Ok(())
+        }
+    }
+
+    /// Unwraps the inner [`File`], failing if it may be in an inconsistent
+    /// state.
+    ///
+    /// On platforms without positional I/O, operations lock a mutex around
+    /// the file, and a panic during one of them poisons it. Unlike
+    /// [`into_inner`](Self::into_inner), which silently ignores this, this
+    /// method returns an error in this case. On other platforms, this never
+    /// fails.
+    ///
+    /// The file's cursor position is unspecified.
+    #[inline]
+    pub fn try_into_inner(self) -> io::Result<File> {
+        #[cfg(any(unix, target_os = "windows", target_os = "wasi"))]
+        {
+            Ok(self.0)
+        }
+
+        #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
+        {
+            self.0
+                .into_inner()
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "file mutex was poisoned"))
+        }
+    }
+}
+
+/// What kind of file a [`RandomAccessFile`] is.
+///
+/// This is returned by [`RandomAccessFile::capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether this is a regular file.
+    pub is_regular: bool,
+    /// Whether this is a block device. This is always `false` on platforms
+    /// other than Unix.
+    pub is_block_device: bool,
+    /// Whether the file supports seeking, and thus positional I/O without
+    /// emulation. This is `false` for pipes, sockets and some terminals.
+    pub is_seekable: bool,
+    /// The block size for efficient I/O, as reported by the OS. This is only
+    /// available on Unix.
+    pub preferred_block_size: Option<u64>,
+}
+
+impl RandomAccessFile {
+    #[cfg(unix)]
+    fn raw_read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.0.read_exact_at(buf, offset)
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "wasi"))]
+    fn raw_read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        crate::default_read_exact_at(self, buf, offset)
+    }
+
+    #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
+    fn raw_read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        use io::{Read, Seek};
+
+        let file = &mut *self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        file.seek(io::SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
 }
 
 impl ReadAt for RandomAccessFile {
@@ -221,18 +538,12 @@ impl ReadAt for RandomAccessFile {
         }
     }
 
-    #[cfg(unix)]
+    /// If the end of file is reached, the error includes the requested range
+    /// and the size of the file.
     fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
-        self.0.read_exact_at(buf, offset)
-    }
-
-    #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
-    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
-        use io::{Read, Seek};
-
-        let file = &mut *self.0.lock().unwrap_or_else(PoisonError::into_inner);
-        file.seek(io::SeekFrom::Start(offset))?;
-        file.read_exact(buf)
+        let len = buf.len();
+        self.raw_read_exact_at(buf, offset)
+            .map_err(|err| crate::refine_eof_error(self, err, offset, len))
     }
 
     #[cfg(any(target_os = "wasi", target_os = "wasip1"))]
@@ -241,6 +552,38 @@ impl ReadAt for RandomAccessFile {
         self.0.read_vectored_at(bufs, offset)
     }
 
+    /// This reads directly into uninitialized memory on Unix.
+    #[cfg(unix)]
+    fn read_uninit_array_at<const N: usize>(&self, offset: u64) -> io::Result<[u8; N]>
+    where
+        Self: Sized,
+    {
+        let mut buf = std::mem::MaybeUninit::<[u8; N]>::uninit();
+        let ptr = buf.as_mut_ptr().cast::<u8>();
+
+        let mut read = 0;
+        while read < N {
+            // SAFETY: the `N - read` bytes after `ptr + read` are within `buf`.
+            let ret = unsafe { pread(self, ptr.add(read), N - read, offset + read as u64)? };
+            match ret {
+                0 => {
+                    let err = crate::fill_buffer_error();
+                    return Err(crate::refine_eof_error(self, err, offset, N));
+                }
+                -1 => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+                n => read += n as usize,
+            }
+        }
+
+        // SAFETY: all `N` bytes were written by `pread`.
+        Ok(unsafe { buf.assume_init() })
+    }
+
     #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
     fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
         use io::{Read, Seek};
@@ -307,6 +650,253 @@ impl WriteAt for RandomAccessFile {
         use std::io::Write;
         self.with_file(|mut f| f.flush())
     }
+
+    /// Synchronizes the data of the file with
+    /// [`sync_data`](Self::sync_data), and returns its length.
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.sync_data()?;
+        self.len()
+    }
+}
+
+impl Size for RandomAccessFile {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.len()
+    }
+}
+
+/// Reads into uninitialized memory with `pread`.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `len` bytes.
+#[cfg(unix)]
+unsafe fn pread(
+    file: &RandomAccessFile,
+    ptr: *mut u8,
+    len: usize,
+    offset: u64,
+) -> io::Result<isize> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    use libc::{off64_t as off_t, pread64 as pread};
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    use libc::{off_t, pread};
+
+    let offset = off_t::try_from(offset)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))?;
+    Ok(pread(file.as_raw_fd(), ptr.cast(), len, offset))
+}
+
+/// Copies a range with `copy_file_range`.
+///
+/// Returns `None` if nothing could be copied because the files do not
+/// support it. This includes files for which `copy_file_range` copies
+/// nothing even though data remains, like those of procfs or some network
+/// filesystems.
+#[cfg(target_os = "linux")]
+fn copy_file_range(
+    src: &RandomAccessFile,
+    src_offset: u64,
+    dst: &RandomAccessFile,
+    dst_offset: u64,
+    len: u64,
+) -> io::Result<Option<u64>> {
+    let offset = |offset: u64| {
+        libc::off64_t::try_from(offset)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))
+    };
+    let mut src_off = offset(src_offset)?;
+    let mut dst_off = offset(dst_offset)?;
+
+    let mut copied = 0;
+    while copied < len {
+        let chunk = usize::try_from(len - copied).unwrap_or(usize::MAX);
+        let n = unsafe {
+            libc::copy_file_range(
+                src.as_raw_fd(),
+                &mut src_off,
+                dst.as_raw_fd(),
+                &mut dst_off,
+                chunk,
+                0,
+            )
+        };
+
+        match n {
+            // Some filesystems do not support it, but report it as the end of
+            // the file, which is checked by the buffered copy.
+            0 if copied == 0 => return Ok(None),
+            0 => break,
+            n if n > 0 => copied += n as u64,
+            _ => {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(libc::EINTR) => continue,
+                    Some(libc::EXDEV | libc::ENOSYS | libc::EOPNOTSUPP | libc::EINVAL)
+                        if copied == 0 =>
+                    {
+                        return Ok(None)
+                    }
+                    _ => return Err(err),
+                }
+            }
+        }
+    }
+
+    Ok(Some(copied))
+}
+
+/// Copies a range through a buffer.
+fn copy_range_buffered(
+    src: &RandomAccessFile,
+    mut src_offset: u64,
+    dst: &RandomAccessFile,
+    mut dst_offset: u64,
+    len: u64,
+) -> io::Result<u64> {
+    let mut buf = vec![0; len.min(64 * 1024) as usize];
+
+    let mut copied = 0;
+    while copied < len {
+        let chunk = (len - copied).min(buf.len() as u64) as usize;
+        let n = match src.read_at(&mut buf[..chunk], src_offset) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        dst.write_all_at(&buf[..n], dst_offset)?;
+
+        src_offset += n as u64;
+        dst_offset += n as u64;
+        copied += n as u64;
+    }
+
+    Ok(copied)
+}
+
+/// Returns a pair of numbers that identifies the file on disk.
+#[cfg(unix)]
+fn file_id(file: &RandomAccessFile) -> io::Result<(u64, u64)> {
+    let metadata = file.metadata()?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+/// Returns a pair of numbers that identifies the file on disk.
+#[cfg(target_os = "windows")]
+fn file_id(file: &RandomAccessFile) -> io::Result<(u64, u64)> {
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let index = (info.nFileIndexHigh as u64) << 32 | info.nFileIndexLow as u64;
+    Ok((info.dwVolumeSerialNumber as u64, index))
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn file_id(_: &RandomAccessFile) -> io::Result<(u64, u64)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "file identity is not supported on this platform",
+    ))
+}
+
+/// Opens the file of `file` again, in read-only mode.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn reopen_readonly(file: &RandomAccessFile) -> io::Result<File> {
+    File::open(format!("/proc/self/fd/{}", file.as_raw_fd()))
+}
+
+/// Opens the file of `file` again, in read-only mode.
+#[cfg(target_os = "windows")]
+fn reopen_readonly(file: &RandomAccessFile) -> io::Result<File> {
+    use windows_sys::Win32::{
+        Foundation::{GENERIC_READ, INVALID_HANDLE_VALUE},
+        Storage::FileSystem::{ReOpenFile, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE},
+    };
+
+    let share = FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE;
+    let handle = unsafe { ReOpenFile(file.as_raw_handle() as _, GENERIC_READ, share, 0) };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_handle(handle as _) })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "windows")))]
+fn reopen_readonly(_: &RandomAccessFile) -> io::Result<File> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reopening a file is not supported on this platform",
+    ))
+}
+
+/// Calls `f` with a `File` borrowing a file descriptor, which is not closed
+/// afterwards.
+#[cfg(any(unix, target_os = "wasi"))]
+fn with_borrowed_file<T>(fd: BorrowedFd<'_>, f: impl FnOnce(&File) -> T) -> T {
+    // SAFETY: the descriptor stays open while it is borrowed, and
+    // `ManuallyDrop` prevents the `File` from closing it.
+    let file = std::mem::ManuallyDrop::new(unsafe { File::from_raw_fd(fd.as_raw_fd()) });
+    f(&file)
+}
+
+/// Reads from the file descriptor with `pread`, without changing its cursor.
+#[cfg(any(unix, target_os = "wasi"))]
+impl ReadAt for OwnedFd {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        with_borrowed_file(self.as_fd(), |f| f.read_at(buf, offset))
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        with_borrowed_file(self.as_fd(), |f| f.read_exact_at(buf, offset))
+    }
+}
+
+/// Writes to the file descriptor with `pwrite`, without changing its cursor.
+#[cfg(any(unix, target_os = "wasi"))]
+impl WriteAt for OwnedFd {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        with_borrowed_file(self.as_fd(), |f| f.write_at(buf, offset))
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        with_borrowed_file(self.as_fd(), |f| f.write_all_at(buf, offset))
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        with_borrowed_file(self.as_fd(), |f| {
+            f.sync_data()?;
+            Ok(f.metadata()?.len())
+        })
+    }
+}
+
+/// Synchronizes several files to disk, one after the other.
+///
+/// Each file is synchronized with [`RandomAccessFile::sync_all`] in the given
+/// order, and this stops on the first error, so that a file is only
+/// synchronized once the previous ones are durable. This can be used to make a
+/// write-ahead log durable before the data it describes.
+///
+/// This only orders synchronizations: writes that depend on the durability of
+/// these files must not be issued before this function returns.
+pub fn sync_all_ordered(files: &[&RandomAccessFile]) -> io::Result<()> {
+    for file in files {
+        file.sync_all()?;
+    }
+    Ok(())
 }
 
 impl From<File> for RandomAccessFile {
@@ -451,6 +1041,66 @@ impl SyncFile {
         Ok(SyncFile::from(f))
     }
 
+    /// Attempts to open a file in read-only mode, with its cursor at the given
+    /// offset.
+    ///
+    /// The offset may be past the end of the file, in which case reads will
+    /// return short. See [`File::open`] for details.
+    #[inline]
+    pub fn open_at_offset<P: AsRef<Path>>(path: P, offset: u64) -> io::Result<SyncFile> {
+        let f = File::open(path.as_ref())?;
+        Ok(SyncFile::from_file_at_offset(f, offset))
+    }
+
+    /// Creates a new `SyncFile` from an open [`File`], with its cursor at the
+    /// given offset.
+    ///
+    /// The offset may be past the end of the file, in which case reads will
+    /// return short.
+    #[inline]
+    pub fn from_file_at_offset(file: File, offset: u64) -> SyncFile {
+        let file = Arc::new(RandomAccessFile::from(file));
+        SyncFile(Adapter::with_offset(file, offset))
+    }
+
+    /// Creates a new `SyncFile` from a [`RandomAccessFile`] and the offset of
+    /// its cursor.
+    ///
+    /// This is the inverse of [`into_raw_parts`](Self::into_raw_parts).
+    #[inline]
+    pub fn from_raw_parts(file: RandomAccessFile, offset: u64) -> SyncFile {
+        SyncFile(Adapter::with_offset(Arc::new(file), offset))
+    }
+
+    /// Decomposes this file into its [`RandomAccessFile`] and the offset of
+    /// its cursor.
+    ///
+    /// This only succeeds if this is the only handle to the underlying file.
+    /// Otherwise, this file is returned unchanged, as other clones or halves
+    /// still use the `RandomAccessFile`.
+    pub fn into_raw_parts(self) -> Result<(RandomAccessFile, u64), SyncFile> {
+        let offset = self.offset();
+        match Arc::try_unwrap(self.0.into_inner()) {
+            Ok(file) => Ok((file, offset)),
+            Err(file) => Err(SyncFile(Adapter::with_offset(file, offset))),
+        }
+    }
+
+    /// Closes the file if this is its last handle, returning any error
+    /// reported by the OS.
+    ///
+    /// If other handles to the file exist, such as clones or halves of this
+    /// one, this only drops this handle and returns `Ok(())`: the file stays
+    /// open until the last handle is closed or dropped.
+    ///
+    /// See [`RandomAccessFile::close`] for details.
+    pub fn close(self) -> io::Result<()> {
+        match self.into_raw_parts() {
+            Ok((file, _)) => file.close(),
+            Err(_) => Ok(()),
+        }
+    }
+
     /// Returns the offset used when reading the file.
     ///
     /// This is equivalent to [`io::Seek::stream_position`] but does not use a
@@ -459,6 +1109,98 @@ impl SyncFile {
     pub fn offset(&self) -> u64 {
         self.0.offset()
     }
+
+    /// Creates a clone of this file that reads ahead `window` bytes when
+    /// used as a stream.
+    ///
+    /// See [`ReadaheadFile`] for details.
+    #[must_use]
+    #[inline]
+    pub fn with_readahead(&self, window: usize) -> ReadaheadFile {
+        ReadaheadFile::new(self.clone(), window)
+    }
+
+    /// Advances the cursor after `n` bytes were read from it.
+    #[inline]
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.0.consume(n);
+    }
+
+    /// Opens this file again in read-only mode.
+    ///
+    /// The returned file has the cursor of this file, and is wrapped in
+    /// [`ReadOnly`] so that writing to it does not compile. This file is left
+    /// untouched, so it must be dropped to actually give up write access.
+    ///
+    /// ```compile_fail
+    /// use sync_file::{SyncFile, WriteAt};
+    ///
+    /// let file = SyncFile::open("Cargo.toml")?;
+    /// let file = file.reopen_readonly()?;
+    /// file.write_at(b"Hello", 0)?;
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    ///
+    /// The access mode of an open file cannot be changed, so a new one is
+    /// opened for the same file: through `/proc/self/fd` on Linux and
+    /// Android, and with `ReOpenFile` on Windows. This works even if the file
+    /// was renamed or removed since it was opened. Other platforms return an
+    /// error of kind [`io::ErrorKind::Unsupported`].
+    pub fn reopen_readonly(&self) -> io::Result<ReadOnly<SyncFile>> {
+        let file = RandomAccessFile::from(reopen_readonly(self.0.get_ref())?);
+        let file = Adapter::with_offset(Arc::new(file), self.offset());
+        Ok(ReadOnly::new(SyncFile(file)))
+    }
+
+    /// Truncates the file at the current offset.
+    ///
+    /// After writing a payload sequentially to a file that may have existed
+    /// before, this removes the bytes left after it, so that the file holds
+    /// exactly what was written. The file is truncated at the current cursor,
+    /// not at the end of the furthest write: bytes written before seeking
+    /// back are removed too.
+    ///
+    /// If the cursor is past the end of the file, the file is extended
+    /// instead.
+    #[inline]
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.set_len(self.offset())
+    }
+
+    /// Returns `true` if both files share the same [`RandomAccessFile`], which
+    /// is the case of clones.
+    ///
+    /// This is a cheap pointer comparison. See [`same_fd`](Self::same_fd) to
+    /// compare files opened independently.
+    #[must_use]
+    #[inline]
+    pub fn same_file(&self, other: &SyncFile) -> bool {
+        Arc::ptr_eq(self.0.get_ref(), other.0.get_ref())
+    }
+
+    /// Returns whether both files refer to the same file on disk.
+    ///
+    /// Unlike [`same_file`](Self::same_file), this also returns `true` for
+    /// files opened independently, such as by opening the same path twice.
+    /// Files are identified by their device and inode numbers on Unix, and by
+    /// their volume serial number and file index on Windows. Other platforms
+    /// return an error of kind [`io::ErrorKind::Unsupported`] unless both
+    /// files share the same `RandomAccessFile`.
+    pub fn same_fd(&self, other: &SyncFile) -> io::Result<bool> {
+        if self.same_file(other) {
+            return Ok(true);
+        }
+        Ok(file_id(self)? == file_id(other)?)
+    }
+
+    /// Splits this file into a read half and a write half.
+    ///
+    /// Both halves share the underlying file but have their own cursor, which
+    /// starts at the current offset of this file.
+    pub fn split(self) -> (ReadHalf, WriteHalf) {
+        let write = self.0.clone();
+        (ReadHalf(self.0), WriteHalf(write))
+    }
 }
 
 impl std::ops::Deref for SyncFile {
@@ -507,6 +1249,18 @@ impl WriteAt for SyncFile {
     fn flush(&self) -> io::Result<()> {
         self.0.flush()
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.0.flush_tracked()
+    }
+}
+
+impl Size for SyncFile {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.0.size()
+    }
 }
 
 impl io::Read for SyncFile {
@@ -529,16 +1283,10 @@ impl io::Read for SyncFile {
 impl io::Seek for SyncFile {
     #[inline]
     fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
-        let pos = match pos {
-            // Override `Adapter`'s implementation to support seeking to the end of file.
-            io::SeekFrom::End(_) => {
-                let offset = self.0.get_ref().with_file(|mut f| f.seek(pos))?;
-                io::SeekFrom::Start(offset)
-            }
-            pos => pos,
-        };
-
-        self.0.seek(pos)
+        match pos {
+            io::SeekFrom::End(p) => self.0.seek_from_end(p),
+            pos => io::Seek::seek(&mut self.0, pos),
+        }
     }
 
     #[inline]
@@ -666,3 +1414,471 @@ impl fmt::Debug for SyncFile {
             .finish()
     }
 }
+
+/// The read half of a [`SyncFile`], created by [`SyncFile::split`].
+pub struct ReadHalf(Adapter<Arc<RandomAccessFile>>);
+
+impl ReadHalf {
+    /// Returns the offset used when reading the file.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.0.offset()
+    }
+}
+
+impl ReadAt for ReadHalf {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.0.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.0.read_exact_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        self.0.read_vectored_at(bufs, offset)
+    }
+}
+
+impl io::Read for ReadHalf {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    #[inline]
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buf)
+    }
+
+    #[inline]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
+}
+
+impl io::Seek for ReadHalf {
+    #[inline]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match pos {
+            io::SeekFrom::End(p) => self.0.seek_from_end(p),
+            pos => io::Seek::seek(&mut self.0, pos),
+        }
+    }
+
+    #[inline]
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.offset())
+    }
+}
+
+impl fmt::Debug for ReadHalf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadHalf")
+            .field("file", self.0.get_ref())
+            .field("offset", &self.offset())
+            .finish()
+    }
+}
+
+/// The write half of a [`SyncFile`], created by [`SyncFile::split`].
+pub struct WriteHalf(Adapter<Arc<RandomAccessFile>>);
+
+impl WriteHalf {
+    /// Returns the offset used when writing the file.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.0.offset()
+    }
+
+    /// Truncates the file at the current offset.
+    ///
+    /// See [`SyncFile::finish`] for details.
+    #[inline]
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.0.get_ref().set_len(self.offset())
+    }
+}
+
+impl WriteAt for WriteHalf {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.0.write_at(buf, offset)
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.0.write_all_at(buf, offset)
+    }
+
+    #[inline]
+    fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        self.0.write_vectored_at(bufs, offset)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        WriteAt::flush(&self.0)
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.0.flush_tracked()
+    }
+}
+
+impl io::Write for WriteHalf {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf)
+    }
+
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(&mut self.0)
+    }
+}
+
+impl io::Seek for WriteHalf {
+    #[inline]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match pos {
+            io::SeekFrom::End(p) => self.0.seek_from_end(p),
+            pos => io::Seek::seek(&mut self.0, pos),
+        }
+    }
+
+    #[inline]
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.offset())
+    }
+}
+
+impl fmt::Debug for WriteHalf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteHalf")
+            .field("file", self.0.get_ref())
+            .field("offset", &self.offset())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+    use std::io::prelude::*;
+
+    #[test]
+    fn split_halves() {
+        let path = TempPath::new("split_halves");
+        let (mut read, mut write) = SyncFile::from(path.create_rw()).split();
+
+        std::thread::spawn(move || write.write_all(b"Hello World!"))
+            .join()
+            .unwrap()
+            .unwrap();
+
+        let mut buf = [0; 5];
+        read.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        assert_eq!(read.offset(), 5);
+        read.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+    }
+
+    #[test]
+    fn readahead() {
+        let contents = fs::read("LICENSE-APACHE").unwrap();
+        let file = SyncFile::open("LICENSE-APACHE").unwrap();
+        let mut f = file.with_readahead(256);
+
+        let mut buf = [0; 100];
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &contents[..100]);
+        assert_eq!(f.offset(), 100);
+        assert_eq!(file.offset(), 0);
+
+        f.seek(io::SeekFrom::Start(300)).unwrap();
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &contents[300..400]);
+
+        let mut rest = Vec::new();
+        f.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &contents[400..]);
+    }
+
+    #[test]
+    fn finish() {
+        let path = TempPath::new("finish");
+        std::fs::write(&path, b"Hello World!").unwrap();
+
+        let mut f = SyncFile::from(File::options().write(true).open(&path).unwrap());
+        f.write_all(b"Bye!").unwrap();
+        assert_eq!(f.size().unwrap(), 12);
+        f.finish().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"Bye!");
+    }
+
+    #[test]
+    fn same_file() {
+        let a = SyncFile::open("LICENSE-MIT").unwrap();
+        let clone = a.clone();
+        assert!(a.same_file(&clone));
+        assert!(a.same_fd(&clone).unwrap());
+
+        let b = SyncFile::open("LICENSE-MIT").unwrap();
+        assert!(!a.same_file(&b));
+        assert!(a.same_fd(&b).unwrap());
+
+        let other = SyncFile::open("LICENSE-APACHE").unwrap();
+        assert!(!a.same_file(&other));
+        assert!(!a.same_fd(&other).unwrap());
+    }
+
+    #[test]
+    fn raw_parts() {
+        let mut f = SyncFile::open("LICENSE-APACHE").unwrap();
+        f.seek(io::SeekFrom::Start(4)).unwrap();
+
+        let clone = f.clone();
+        let f = f.into_raw_parts().unwrap_err();
+        drop(clone);
+
+        let (file, offset) = f.into_raw_parts().unwrap();
+        assert_eq!(offset, 4);
+
+        let mut f = SyncFile::from_raw_parts(file, offset);
+        let mut buf = [0; 5];
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"right");
+    }
+
+    #[test]
+    fn close() {
+        let path = TempPath::new("close");
+        let file = SyncFile::from(path.create_rw());
+        file.write_all_at(b"Hello World!", 0).unwrap();
+
+        let clone = file.clone();
+        file.close().unwrap();
+        let mut buf = [0; 5];
+        clone.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+
+        clone.close().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"Hello World!");
+    }
+
+    #[test]
+    fn open_at_offset() {
+        let mut f = SyncFile::open_at_offset("LICENSE-APACHE", 4).unwrap();
+        assert_eq!(f.offset(), 4);
+
+        let mut buf = [0; 5];
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"right");
+        assert_eq!(f.offset(), 9);
+
+        let mut f = SyncFile::open_at_offset("LICENSE-APACHE", 1 << 40).unwrap();
+        assert_eq!(f.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn len() {
+        let path = TempPath::new("len");
+        let file = RandomAccessFile::from(path.create_rw());
+        assert!(file.is_empty().unwrap());
+
+        file.write_all_at(b"Hello World!", 1000).unwrap();
+        assert_eq!(file.len().unwrap(), 1012);
+        assert_eq!(file.len().unwrap(), file.metadata().unwrap().len());
+    }
+
+    #[test]
+    fn flush_tracked() {
+        let path = TempPath::new("flush_tracked");
+        let file = SyncFile::from(path.create_rw());
+        file.write_all_at(b"Hello World!", 0).unwrap();
+        assert_eq!(file.flush_tracked().unwrap(), 12);
+
+        file.write_all_at(b"!", 20).unwrap();
+        assert_eq!(file.flush_tracked().unwrap(), 21);
+    }
+
+    #[test]
+    fn copy_range_to() {
+        let src_path = TempPath::new("copy_range_to_src");
+        let dst_path = TempPath::new("copy_range_to_dst");
+        let src = RandomAccessFile::from(src_path.create_rw());
+        let dst = RandomAccessFile::from(dst_path.create_rw());
+        src.write_all_at(b"Hello World!", 0).unwrap();
+        dst.write_all_at(b"Rust: ......", 0).unwrap();
+
+        assert_eq!(src.copy_range_to(6, &dst, 6, 5).unwrap(), 5);
+        assert_eq!(fs::read(&dst_path).unwrap(), b"Rust: World.");
+
+        // Copies stop at the end of the source.
+        assert_eq!(src.copy_range_to(6, &dst, 20, 100).unwrap(), 6);
+        assert_eq!(dst.len().unwrap(), 26);
+
+        assert_eq!(copy_range_buffered(&src, 0, &dst, 0, 5).unwrap(), 5);
+        assert_eq!(copy_range_buffered(&src, 10, &dst, 6, 10).unwrap(), 2);
+        assert_eq!(&fs::read(&dst_path).unwrap()[..12], b"Hello d!rld.");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn copy_range_from_procfs() {
+        let dst_path = TempPath::new("copy_range_from_procfs");
+        let src = RandomAccessFile::open("/proc/self/mounts").unwrap();
+        let dst = RandomAccessFile::from(dst_path.create_rw());
+
+        // Recent kernels copy nothing from procfs files with `copy_file_range`.
+        assert!(src.copy_range_to(0, &dst, 0, 4).unwrap() > 0);
+        assert!(!fs::read(&dst_path).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn capabilities() {
+        let file = RandomAccessFile::open("LICENSE-MIT").unwrap();
+        let capabilities = file.capabilities().unwrap();
+        assert!(capabilities.is_regular);
+        assert!(!capabilities.is_block_device);
+        assert!(capabilities.is_seekable);
+
+        let block_size = capabilities.preferred_block_size.unwrap();
+        assert!(block_size >= 512 && block_size.is_power_of_two());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sync_parent_dir() {
+        let path = TempPath::new("sync_parent_dir");
+        let file = RandomAccessFile::create(&path).unwrap();
+        file.sync_all().unwrap();
+        RandomAccessFile::sync_parent_dir(&path).unwrap();
+    }
+
+    #[test]
+    fn from_file_checked() {
+        let file = File::open("LICENSE-MIT").unwrap();
+        RandomAccessFile::from_file_checked(file).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_file_checked_pipe() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (read, _write) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+        let err = RandomAccessFile::from_file_checked(read).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn owned_fd() {
+        let path = TempPath::new("owned_fd");
+        let fd = OwnedFd::from(path.create_rw());
+
+        fd.write_all_at(b"Hello World!", 0).unwrap();
+        let mut buf = [0; 5];
+        fd.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+
+        let mut file = File::from(fd);
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Hello World!");
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "windows"))]
+    #[test]
+    fn reopen_readonly() {
+        let path = TempPath::new("reopen_readonly");
+        let mut file = SyncFile::from(path.create_rw());
+        file.write_all(b"Hello World!").unwrap();
+        file.seek(io::SeekFrom::Start(6)).unwrap();
+
+        let mut readonly = file.reopen_readonly().unwrap();
+        drop(file);
+        let mut buf = String::new();
+        readonly.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "World!");
+
+        let mut buf = [0; 5];
+        readonly.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello");
+        assert_eq!(readonly.seek(io::SeekFrom::Current(-3)).unwrap(), 9);
+        assert_eq!(readonly.size().unwrap(), 12);
+    }
+
+    #[test]
+    fn sync_all_ordered() {
+        let (wal_path, data_path) = (TempPath::new("sync_wal"), TempPath::new("sync_data"));
+        let wal = RandomAccessFile::from(wal_path.create_rw());
+        let data = RandomAccessFile::from(data_path.create_rw());
+
+        wal.write_all_at(b"Hello", 0).unwrap();
+        data.write_all_at(b"World", 0).unwrap();
+        super::sync_all_ordered(&[&wal, &data]).unwrap();
+    }
+
+    #[test]
+    fn errno_kinds() {
+        assert_eq!(errno_kind(errno::NOENT), Some(io::ErrorKind::NotFound));
+        assert_eq!(
+            errno_kind(errno::ACCES),
+            Some(io::ErrorKind::PermissionDenied)
+        );
+        assert_eq!(
+            errno_kind(errno::NOTCAPABLE),
+            Some(io::ErrorKind::PermissionDenied)
+        );
+        assert_eq!(errno_kind(errno::BADF), Some(io::ErrorKind::InvalidInput));
+        assert_eq!(errno_kind(errno::INVAL), Some(io::ErrorKind::InvalidInput));
+        assert_eq!(errno_kind(errno::NOSYS), Some(io::ErrorKind::Unsupported));
+        // `NOSPC` is left to the standard library.
+        assert_eq!(errno_kind(51), None);
+    }
+
+    #[test]
+    fn try_into_inner() {
+        let file = RandomAccessFile::open("LICENSE-MIT").unwrap();
+        let file = file.try_into_inner().unwrap();
+        assert_eq!(file.metadata().unwrap().len(), 1058);
+    }
+
+    #[test]
+    #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
+    fn try_into_inner_poisoned() {
+        let file = Arc::new(RandomAccessFile::open("LICENSE-MIT").unwrap());
+
+        let poisoner = file.clone();
+        let res = std::thread::spawn(move || {
+            let _guard = poisoner.0.lock().unwrap();
+            panic!("poisoning the file mutex");
+        })
+        .join();
+        assert!(res.is_err());
+
+        let file = Arc::try_unwrap(file).unwrap();
+        assert!(file.try_into_inner().is_err());
+    }
+}
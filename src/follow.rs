@@ -0,0 +1,121 @@
+use crate::{RandomAccessFile, ReadAt};
+use std::{io, thread, time::Duration};
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// An iterator over the bytes appended to a file, like `tail -f`.
+///
+/// This struct is created by [`RandomAccessFile::follow`].
+#[derive(Debug)]
+pub struct FollowIter<'a> {
+    file: &'a RandomAccessFile,
+    offset: u64,
+    poll_interval: Duration,
+}
+
+impl<'a> FollowIter<'a> {
+    #[inline]
+    pub(crate) fn new(file: &'a RandomAccessFile, start: u64, poll_interval: Duration) -> Self {
+        Self {
+            file,
+            offset: start,
+            poll_interval,
+        }
+    }
+
+    /// Returns the offset of the next byte to be yielded.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn read_next(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            let len = self.file.len()?;
+            if len < self.offset {
+                self.offset = 0;
+            }
+            if len == self.offset {
+                thread::sleep(self.poll_interval);
+                continue;
+            }
+
+            let mut buf = vec![0; (len - self.offset).min(CHUNK_SIZE) as usize];
+            let n = self.file.read_at(&mut buf, self.offset)?;
+            if n == 0 {
+                // The file was truncated since its length was queried.
+                continue;
+            }
+            buf.truncate(n);
+            self.offset += n as u64;
+            return Ok(buf);
+        }
+    }
+}
+
+impl Iterator for FollowIter<'_> {
+    type Item = io::Result<Vec<u8>>;
+
+    /// Waits for new bytes and returns them.
+    ///
+    /// This never returns `None`.
+    #[inline]
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        Some(self.read_next())
+    }
+}
+
+impl RandomAccessFile {
+    /// Returns an iterator over the bytes appended to the file from `start`.
+    ///
+    /// The iterator polls the length of the file every `poll_interval`, and
+    /// yields new bytes as soon as it grows, in chunks of at most 64 KiB. It
+    /// never ends, so it is meant to be stopped by the caller, and errors do
+    /// not stop it either: the next call tries again.
+    ///
+    /// If the file becomes shorter than the offset of the iterator, it is
+    /// assumed to have been truncated to be rewritten, and the iterator starts
+    /// again from the beginning of the file.
+    #[inline]
+    pub fn follow(&self, start: u64, poll_interval: Duration) -> FollowIter<'_> {
+        FollowIter::new(self, start, poll_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::TempPath, WriteAt};
+    use std::sync::Arc;
+
+    #[test]
+    fn follow() {
+        let path = TempPath::new("follow");
+        let file = Arc::new(RandomAccessFile::from(path.create_rw()));
+        file.write_all_at(b"old ", 0).unwrap();
+
+        let writer = file.clone();
+        let thread = thread::spawn(move || {
+            let mut offset = 4;
+            for word in ["Hello", " ", "World", "!"] {
+                thread::sleep(Duration::from_millis(5));
+                writer.write_all_at(word.as_bytes(), offset).unwrap();
+                offset += word.len() as u64;
+            }
+        });
+
+        let mut follow = file.follow(4, Duration::from_millis(1));
+        let mut received = Vec::new();
+        while received.len() < 12 {
+            received.extend(follow.next().unwrap().unwrap());
+        }
+        thread.join().unwrap();
+
+        assert_eq!(received, b"Hello World!");
+        assert_eq!(follow.offset(), 16);
+
+        file.set_len(3).unwrap();
+        assert_eq!(follow.next().unwrap().unwrap(), b"old");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{pod::read_struct_at, OffsetReader, ReadAt, WriteAt};
+use std::{fmt, io, marker::PhantomData, mem::size_of};
+
+/// A source made of a fixed-size header followed by a body.
+///
+/// The header is a value of type `H` stored at offset 0, as it is laid out in
+/// memory, so it is native-endian. The body is made of all the bytes after
+/// it, and can be accessed with [`body`](Self::body) as a source of its own.
+pub struct Framed<H, R> {
+    inner: R,
+    _marker: PhantomData<fn() -> H>,
+}
+
+impl<H, R> Framed<H, R> {
+    /// Creates a new `Framed` source.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the length of the header, which is the offset of the body.
+    #[must_use]
+    #[inline]
+    pub fn header_len(&self) -> u64 {
+        size_of::<H>() as u64
+    }
+
+    /// Returns a view of the body, whose offsets are relative to the start
+    /// of the body.
+    ///
+    /// The size of the body is zero if the source is shorter than the header.
+    #[inline]
+    pub fn body(&self) -> OffsetReader<&R> {
+        OffsetReader::new(&self.inner, self.header_len())
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<H: bytemuck::Pod, R: ReadAt> Framed<H, R> {
+    /// Reads the header.
+    #[inline]
+    pub fn header(&self) -> io::Result<H> {
+        read_struct_at(&self.inner, 0)
+    }
+}
+
+impl<H: bytemuck::Pod, R: WriteAt> Framed<H, R> {
+    /// Writes the header.
+    #[inline]
+    pub fn set_header(&self, header: &H) -> io::Result<()> {
+        self.inner.write_all_at(bytemuck::bytes_of(header), 0)
+    }
+}
+
+impl<H, R: fmt::Debug> fmt::Debug for Framed<H, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Framed")
+            .field("inner", &self.inner)
+            .field("header_len", &self.header_len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::MemFile, Size};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Header {
+        magic: [u8; 4],
+        len: u32,
+    }
+
+    unsafe impl bytemuck::Zeroable for Header {}
+    unsafe impl bytemuck::Pod for Header {}
+
+    #[test]
+    fn header_and_body() {
+        let framed = Framed::<Header, _>::new(MemFile::default());
+        assert_eq!(framed.header_len(), 8);
+
+        let header = Header {
+            magic: *b"SYNC",
+            len: 12,
+        };
+        framed.set_header(&header).unwrap();
+        framed.body().write_all_at(b"Hello World!", 0).unwrap();
+        assert_eq!(framed.header().unwrap(), header);
+
+        let body = framed.body();
+        assert_eq!(body.size().unwrap(), 12);
+        let mut buf = [0; 5];
+        body.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+        assert_eq!(&framed.get_ref().contents()[..4], b"SYNC");
+    }
+}
@@ -0,0 +1,117 @@
+use std::{hash::Hasher, io};
+
+/// A reader that feeds every byte it returns to a [`Hasher`].
+///
+/// This is meant to wrap a sequential reader, such as an [`Adapter`] or a
+/// [`SyncFile`]: bytes are hashed in the order they are read, so the digest
+/// only matches the one of the whole content if the stream is consumed from
+/// start to end without seeking.
+///
+/// [`Adapter`]: crate::Adapter
+/// [`SyncFile`]: crate::SyncFile
+#[derive(Debug, Clone, Copy)]
+pub struct HashingReader<R, H> {
+    inner: R,
+    hasher: H,
+}
+
+impl<R, H> HashingReader<R, H> {
+    /// Creates a new `HashingReader`.
+    #[inline]
+    pub fn new(inner: R, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a reference to the hasher.
+    #[inline]
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Unwraps the inner reader and the hasher.
+    #[inline]
+    pub fn into_inner(self) -> (R, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<R, H: Hasher> HashingReader<R, H> {
+    /// Returns the digest of the bytes read so far.
+    #[must_use]
+    #[inline]
+    pub fn digest(&self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<R, H> io::Read for HashingReader<R, H>
+where
+    R: io::Read,
+    H: Hasher,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.write(&buf[..read]);
+        Ok(read)
+    }
+
+    #[inline]
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.hasher.write(buf);
+        Ok(())
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        let read = self.inner.read_vectored(bufs)?;
+
+        let mut remaining = read;
+        for buf in bufs.iter() {
+            if remaining == 0 {
+                break;
+            }
+            let len = remaining.min(buf.len());
+            self.hasher.write(&buf[..len]);
+            remaining -= len;
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyncFile;
+    use std::{collections::hash_map::DefaultHasher, io::Read};
+
+    #[test]
+    fn sequential_digest() {
+        let contents = std::fs::read("LICENSE-APACHE").unwrap();
+        let mut expected = DefaultHasher::new();
+        expected.write(&contents);
+
+        let file = SyncFile::open("LICENSE-APACHE").unwrap();
+        let mut reader = HashingReader::new(file, DefaultHasher::new());
+
+        let mut read = Vec::new();
+        let mut buf = [0; 100];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            read.extend_from_slice(&buf[..n]);
+        }
+
+        assert_eq!(read, contents);
+        assert_eq!(reader.digest(), expected.finish());
+    }
+}
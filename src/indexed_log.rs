@@ -0,0 +1,233 @@
+use crate::{read_full, ReadAt, Size, WriteAt};
+use std::{
+    convert::TryInto,
+    io,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+const ENTRY_LEN: usize = 8;
+/// The number of entries read at once when loading the index.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// An append-only log of records, with an index of their offsets.
+///
+/// Records are appended one after the other to the data target, and the end
+/// offset of each record is appended to the index target as a little-endian
+/// `u64`. The record `n` thus spans from the end of the record `n - 1` to its
+/// own end, and can be found without scanning the log.
+///
+/// The index is kept in memory too, and loaded by [`open`](Self::open).
+///
+/// # Durability
+///
+/// Durability relies on the [`flush_tracked`](WriteAt::flush_tracked) method
+/// of the targets, which syncs a [`RandomAccessFile`](crate::RandomAccessFile)
+/// to disk, so both targets must support it: a record is synced to the data
+/// target before its entry is written to the index, which is then synced. The
+/// index thus never refers to data that is not durable. If an append is
+/// interrupted, the data written after the last indexed record is ignored and
+/// overwritten by the next append, and a partial trailing entry in the index
+/// is ignored.
+#[derive(Debug)]
+pub struct IndexedLog<W, I = W> {
+    data: W,
+    index: I,
+    /// The end offset of each record.
+    ends: Mutex<Vec<u64>>,
+}
+
+impl<W: Size, I: ReadAt> IndexedLog<W, I> {
+    /// Opens an indexed log, loading its index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the offsets
+    /// of the index are not increasing, or if they are past the end of the
+    /// data target.
+    pub fn open(data: W, index: I) -> io::Result<Self> {
+        let data_len = data.size()?;
+        let mut ends = Vec::new();
+        let mut entries = vec![0; READ_CHUNK * ENTRY_LEN];
+        loop {
+            let offset = (ends.len() * ENTRY_LEN) as u64;
+            let read = read_full(&index, &mut entries, offset)?;
+
+            // A partial entry is the trace of an interrupted append.
+            for entry in entries[..read].chunks_exact(ENTRY_LEN) {
+                let end = u64::from_le_bytes(entry.try_into().unwrap());
+                let last = ends.last().copied().unwrap_or(0);
+                if end < last || end > data_len {
+                    return Err(corrupted_index());
+                }
+                ends.push(end);
+            }
+
+            if read < entries.len() {
+                break;
+            }
+        }
+
+        Ok(Self {
+            data,
+            index,
+            ends: Mutex::new(ends),
+        })
+    }
+}
+
+impl<W, I> IndexedLog<W, I> {
+    /// Returns the number of records.
+    #[must_use]
+    pub fn record_count(&self) -> u64 {
+        self.lock().len() as u64
+    }
+
+    /// Returns the offset of the record `record_no` in the data target, or
+    /// `None` if there is no such record.
+    #[must_use]
+    pub fn offset_of(&self, record_no: u64) -> Option<u64> {
+        self.span(record_no).map(|(start, _)| start)
+    }
+
+    /// Gets a reference to the data target.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.data
+    }
+
+    /// Gets a reference to the index target.
+    #[inline]
+    pub fn index(&self) -> &I {
+        &self.index
+    }
+
+    /// Unwraps the data and index targets.
+    #[inline]
+    pub fn into_inner(self) -> (W, I) {
+        (self.data, self.index)
+    }
+
+    /// Returns the start and end offsets of a record.
+    fn span(&self, record_no: u64) -> Option<(u64, u64)> {
+        let ends = self.lock();
+        let index: usize = record_no.try_into().ok()?;
+        let end = *ends.get(index)?;
+        let start = match index {
+            0 => 0,
+            _ => ends[index - 1],
+        };
+        Some((start, end))
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Vec<u64>> {
+        self.ends.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: WriteAt, I: WriteAt> IndexedLog<W, I> {
+    /// Appends a record to the log, and returns its number.
+    pub fn append_record(&self, record: &[u8]) -> io::Result<u64> {
+        let mut ends = self.lock();
+        let start = ends.last().copied().unwrap_or(0);
+        let end = start.checked_add(record.len() as u64).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "write past the maximum offset")
+        })?;
+
+        self.data.write_all_at(record, start)?;
+        self.data.flush_tracked()?;
+
+        let entry_offset = (ends.len() * ENTRY_LEN) as u64;
+        self.index.write_all_at(&end.to_le_bytes(), entry_offset)?;
+        self.index.flush_tracked()?;
+
+        ends.push(end);
+        Ok(ends.len() as u64 - 1)
+    }
+}
+
+impl<W: ReadAt, I> IndexedLog<W, I> {
+    /// Reads the record `record_no`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if there is no
+    /// such record.
+    pub fn read_record(&self, record_no: u64) -> io::Result<Vec<u8>> {
+        let (start, end) = self
+            .span(record_no)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such record"))?;
+        let len = (end - start)
+            .try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "record too large"))?;
+
+        let mut record = vec![0; len];
+        self.data.read_exact_at(&mut record, start)?;
+        Ok(record)
+    }
+}
+
+#[cold]
+fn corrupted_index() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupted log index")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn append_and_read() {
+        let log = IndexedLog::open(MemFile::default(), MemFile::default()).unwrap();
+        assert_eq!(log.append_record(b"Hello").unwrap(), 0);
+        assert_eq!(log.append_record(b"").unwrap(), 1);
+        assert_eq!(log.append_record(b"World!").unwrap(), 2);
+
+        assert_eq!(log.record_count(), 3);
+        assert_eq!(log.offset_of(0), Some(0));
+        assert_eq!(log.offset_of(2), Some(5));
+        assert_eq!(log.offset_of(3), None);
+        assert_eq!(log.read_record(2).unwrap(), b"World!");
+        assert_eq!(log.read_record(1).unwrap(), b"");
+
+        let err = log.read_record(3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn reopen() {
+        let log = IndexedLog::open(MemFile::default(), MemFile::default()).unwrap();
+        log.append_record(b"Hello").unwrap();
+        log.append_record(b"World!").unwrap();
+
+        // An interrupted append: the data and half of the entry are written.
+        let (data, index) = log.into_inner();
+        data.write_all_at(b"lost", 11).unwrap();
+        index.write_all_at(&[15, 0, 0], 16).unwrap();
+
+        let log = IndexedLog::open(data, index).unwrap();
+        assert_eq!(log.record_count(), 2);
+        assert_eq!(log.read_record(1).unwrap(), b"World!");
+
+        assert_eq!(log.append_record(b"Rust").unwrap(), 2);
+        assert_eq!(log.offset_of(2), Some(11));
+        assert_eq!(log.read_record(2).unwrap(), b"Rust");
+    }
+
+    #[test]
+    fn corrupted_index() {
+        let data = MemFile::default();
+        data.write_all_at(b"Hello", 0).unwrap();
+
+        let index = MemFile::default();
+        index.write_all_at(&(1u64 << 46).to_le_bytes(), 0).unwrap();
+        let err = IndexedLog::open(&data, &index).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        index.write_all_at(&5u64.to_le_bytes(), 0).unwrap();
+        index.write_all_at(&3u64.to_le_bytes(), 8).unwrap();
+        let err = IndexedLog::open(&data, &index).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
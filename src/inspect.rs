@@ -0,0 +1,176 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{fmt, io};
+
+/// A reader that calls a function with the bytes of each read.
+///
+/// This struct is created by [`ReadAt::inspect_reads`].
+pub struct InspectRead<R, F> {
+    inner: R,
+    f: F,
+}
+
+impl<R, F> InspectRead<R, F> {
+    #[inline]
+    pub(crate) fn new(inner: R, f: F) -> Self {
+        Self { inner, f }
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R, F> ReadAt for InspectRead<R, F>
+where
+    R: ReadAt,
+    F: Fn(u64, &[u8]),
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let n = self.inner.read_at(buf, offset)?;
+        (self.f)(offset, &buf[..n]);
+        Ok(n)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)?;
+        (self.f)(offset, buf);
+        Ok(())
+    }
+}
+
+impl<R: Size, F> Size for InspectRead<R, F> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+impl<R: fmt::Debug, F> fmt::Debug for InspectRead<R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InspectRead")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A writer that calls a function with the bytes of each write.
+///
+/// This struct is created by [`WriteAt::inspect_writes`].
+pub struct InspectWrite<W, F> {
+    inner: W,
+    f: F,
+}
+
+impl<W, F> InspectWrite<W, F> {
+    #[inline]
+    pub(crate) fn new(inner: W, f: F) -> Self {
+        Self { inner, f }
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W, F> WriteAt for InspectWrite<W, F>
+where
+    W: WriteAt,
+    F: Fn(u64, &[u8]),
+{
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let n = self.inner.write_at(buf, offset)?;
+        (self.f)(offset, &buf[..n]);
+        Ok(n)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.inner.write_all_at(buf, offset)?;
+        (self.f)(offset, buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<W: Size, F> Size for InspectWrite<W, F> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+impl<W: fmt::Debug, F> fmt::Debug for InspectWrite<W, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InspectWrite")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::MemFile, ReadAt, WriteAt};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn inspect() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let reads = log.clone();
+        let reader = b"Hello World!".inspect_reads(move |offset, buf: &[u8]| {
+            reads.lock().unwrap().push((offset, buf.to_vec()));
+        });
+        let mut buf = [0; 5];
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(reader.read_at(&mut buf, 10).unwrap(), 2);
+
+        let writes = log.clone();
+        let writer = MemFile::default().inspect_writes(move |offset, buf: &[u8]| {
+            writes.lock().unwrap().push((offset, buf.to_vec()));
+        });
+        writer.write_all_at(b"Rust", 3).unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                (6, b"World".to_vec()),
+                (10, b"d!".to_vec()),
+                (3, b"Rust".to_vec()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{crc32::crc32, ReadAt, WriteAt};
+use std::{
+    convert::TryInto,
+    io,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+const HEADER_LEN: u64 = 8;
+const RECORD_HEADER_LEN: usize = 16;
+const CHECKSUM_LEN: usize = 4;
+/// The size of the chunks in which the records are read, so that a corrupted
+/// header does not allocate more than the journal holds.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// A writer that makes groups of writes atomic with a write-ahead journal.
+///
+/// Writes go through a [`Transaction`], which records them in the journal. On
+/// [`commit`](Transaction::commit), the journal is marked as complete, then
+/// writes are applied to the main target and the journal is cleared. If this
+/// is interrupted, [`recover`](Self::recover) applies the complete journal
+/// again, which [`open`](Self::open) does automatically.
+///
+/// Durability relies on the [`flush_tracked`](WriteAt::flush_tracked) method
+/// of the targets, which syncs a [`RandomAccessFile`](crate::RandomAccessFile)
+/// to disk: each step is only started once the previous one is durable, so
+/// both targets must support it.
+///
+/// # Format
+///
+/// The journal starts with the length of the committed records, as a
+/// little-endian `u64`, which is zero when there is nothing to recover. It is
+/// followed by the records, each made of the offset of the write and its
+/// length as little-endian `u64`, the written bytes, and the CRC-32 of all of
+/// these as a little-endian `u32`.
+#[derive(Debug)]
+pub struct Journaled<W, J = W> {
+    main: W,
+    journal: J,
+    lock: Mutex<()>,
+}
+
+impl<W, J> Journaled<W, J>
+where
+    W: WriteAt,
+    J: ReadAt + WriteAt,
+{
+    /// Opens a journaled writer, recovering any complete journal.
+    pub fn open(main: W, journal: J) -> io::Result<Self> {
+        let this = Self {
+            main,
+            journal,
+            lock: Mutex::new(()),
+        };
+        this.recover()?;
+        Ok(this)
+    }
+
+    /// Applies a complete journal to the main target, and clears it.
+    ///
+    /// Returns whether there was something to recover.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a record of
+    /// the journal is corrupted, or if the journal is shorter than its header
+    /// says. In this case, nothing is applied.
+    pub fn recover(&self) -> io::Result<bool> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.replay()
+    }
+
+    fn replay(&self) -> io::Result<bool> {
+        let mut header = [0; HEADER_LEN as usize];
+        match self.journal.read_exact_at(&mut header, 0) {
+            Ok(()) => (),
+            // An empty journal has nothing to recover.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        let len = u64::from_le_bytes(header);
+        if len == 0 {
+            return Ok(false);
+        }
+
+        let len: usize = len.try_into().map_err(|_| corrupted_journal())?;
+        let mut records = Vec::new();
+        while records.len() < len {
+            let start = records.len();
+            records.resize(start + (len - start).min(READ_CHUNK), 0);
+            self.journal
+                .read_exact_at(&mut records[start..], HEADER_LEN + start as u64)
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::UnexpectedEof => corrupted_journal(),
+                    _ => e,
+                })?;
+        }
+
+        // Check all records before applying any of them.
+        let mut writes = Vec::new();
+        let mut rest = &records[..];
+        while !rest.is_empty() {
+            let (offset, data, next) = parse_record(rest).ok_or_else(corrupted_journal)?;
+            writes.push((offset, data));
+            rest = next;
+        }
+
+        for (offset, data) in writes {
+            self.main.write_all_at(data, offset)?;
+        }
+        self.main.flush_tracked()?;
+
+        self.journal.write_all_at(&0u64.to_le_bytes(), 0)?;
+        self.journal.flush_tracked()?;
+        Ok(true)
+    }
+}
+
+impl<W, J> Journaled<W, J> {
+    /// Starts a new transaction.
+    ///
+    /// Only one transaction can be active at a time, so this blocks until the
+    /// current one is committed or dropped.
+    pub fn begin(&self) -> Transaction<'_, W, J> {
+        Transaction {
+            journaled: self,
+            _guard: self.lock.lock().unwrap_or_else(PoisonError::into_inner),
+            end: Mutex::new(HEADER_LEN),
+        }
+    }
+
+    /// Gets a reference to the main target.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.main
+    }
+
+    /// Gets a reference to the journal.
+    #[inline]
+    pub fn journal(&self) -> &J {
+        &self.journal
+    }
+
+    /// Unwraps the main target and the journal.
+    #[inline]
+    pub fn into_inner(self) -> (W, J) {
+        (self.main, self.journal)
+    }
+}
+
+impl<W: ReadAt, J> ReadAt for Journaled<W, J> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.main.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.main.read_exact_at(buf, offset)
+    }
+}
+
+/// A group of writes applied atomically by a [`Journaled`] writer.
+///
+/// Writes are recorded in the journal, and only reach the main target on
+/// [`commit`](Self::commit). Dropping a transaction discards them.
+#[derive(Debug)]
+pub struct Transaction<'a, W, J> {
+    journaled: &'a Journaled<W, J>,
+    _guard: MutexGuard<'a, ()>,
+    end: Mutex<u64>,
+}
+
+impl<W, J> Transaction<'_, W, J>
+where
+    W: WriteAt,
+    J: ReadAt + WriteAt,
+{
+    /// Commits the transaction, applying its writes to the main target.
+    pub fn commit(self) -> io::Result<()> {
+        let journal = &self.journaled.journal;
+        let end = *self.end.lock().unwrap_or_else(PoisonError::into_inner);
+        if end == HEADER_LEN {
+            return Ok(());
+        }
+
+        journal.flush_tracked()?;
+        journal.write_all_at(&(end - HEADER_LEN).to_le_bytes(), 0)?;
+        journal.flush_tracked()?;
+
+        self.journaled.replay()?;
+        Ok(())
+    }
+}
+
+impl<W, J: WriteAt> WriteAt for Transaction<'_, W, J> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + buf.len() + CHECKSUM_LEN);
+        record.extend_from_slice(&offset.to_le_bytes());
+        record.extend_from_slice(&(buf.len() as u64).to_le_bytes());
+        record.extend_from_slice(buf);
+        let checksum = crc32(&record);
+        record.extend_from_slice(&checksum.to_le_bytes());
+
+        // The end of the journal only moves once the record is written, so a
+        // failed write is overwritten by the next one.
+        let mut end = self.end.lock().unwrap_or_else(PoisonError::into_inner);
+        self.journaled.journal.write_all_at(&record, *end)?;
+        *end += record.len() as u64;
+        Ok(())
+    }
+}
+
+/// Parses a record, returning its offset, its data and the following bytes.
+fn parse_record(bytes: &[u8]) -> Option<(u64, &[u8], &[u8])> {
+    let header = bytes.get(..RECORD_HEADER_LEN)?;
+    let offset = u64::from_le_bytes(header[..8].try_into().unwrap());
+    let len: usize = u64::from_le_bytes(header[8..].try_into().unwrap())
+        .try_into()
+        .ok()?;
+
+    let data_end = RECORD_HEADER_LEN.checked_add(len)?;
+    let record_end = data_end.checked_add(CHECKSUM_LEN)?;
+    let checksum = bytes.get(data_end..record_end)?;
+    if crc32(&bytes[..data_end]) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return None;
+    }
+
+    Some((
+        offset,
+        &bytes[RECORD_HEADER_LEN..data_end],
+        &bytes[record_end..],
+    ))
+}
+
+#[cold]
+fn corrupted_journal() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupted journal record")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::MemFile, InspectWrite, RecordingWriter, WriteOp};
+
+    struct Failing;
+
+    impl WriteAt for Failing {
+        fn write_at(&self, _: &[u8], _: u64) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "failing target"))
+        }
+    }
+
+    #[test]
+    fn commit() {
+        let journaled = Journaled::open(MemFile::default(), MemFile::default()).unwrap();
+
+        let tx = journaled.begin();
+        tx.write_all_at(b"World", 6).unwrap();
+        tx.write_all_at(b"Hello ", 0).unwrap();
+        assert_eq!(journaled.get_ref().contents(), b"");
+        tx.commit().unwrap();
+        assert_eq!(journaled.get_ref().contents(), b"Hello World");
+
+        // A dropped transaction is discarded.
+        let tx = journaled.begin();
+        tx.write_all_at(b"Rust!", 6).unwrap();
+        drop(tx);
+        assert!(!journaled.recover().unwrap());
+        assert_eq!(journaled.get_ref().contents(), b"Hello World");
+    }
+
+    #[test]
+    fn sync_order() {
+        let main = RecordingWriter::new(MemFile::default());
+        let journal = RecordingWriter::new(MemFile::default());
+        let checked_main = InspectWrite::new(&main, |_: u64, _: &[u8]| {
+            // The journal is synced before the main target is written.
+            let ops = journal.operations();
+            assert_eq!(ops[ops.len() - 2].offset(), Some(0));
+            assert_eq!(ops[ops.len() - 1], WriteOp::FlushTracked);
+        });
+        let journaled = Journaled::open(checked_main, &journal).unwrap();
+
+        let tx = journaled.begin();
+        tx.write_all_at(b"Hello World", 0).unwrap();
+        tx.commit().unwrap();
+
+        let ops = main.operations();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[1], WriteOp::FlushTracked);
+
+        // The journal is cleared after the main target is synced.
+        let ops = journal.operations();
+        assert_eq!(ops.len(), 6);
+        assert_eq!(ops[4].data(), [0; 8]);
+        assert_eq!(ops[5], WriteOp::FlushTracked);
+    }
+
+    #[test]
+    fn recover() {
+        let journaled = Journaled::open(Failing, MemFile::default()).unwrap();
+        let tx = journaled.begin();
+        tx.write_all_at(b"Hello World", 0).unwrap();
+        assert!(tx.commit().is_err());
+
+        let (_, journal) = journaled.into_inner();
+        let journaled = Journaled::open(MemFile::default(), journal).unwrap();
+        assert_eq!(journaled.get_ref().contents(), b"Hello World");
+        assert!(!journaled.recover().unwrap());
+    }
+
+    #[test]
+    fn corrupted_record() {
+        let journaled = Journaled::open(Failing, MemFile::default()).unwrap();
+        let tx = journaled.begin();
+        tx.write_all_at(b"Hello World", 0).unwrap();
+        assert!(tx.commit().is_err());
+
+        let (_, journal) = journaled.into_inner();
+        journal.write_all_at(b"J", HEADER_LEN + 16).unwrap();
+        let err = Journaled::open(MemFile::default(), journal).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupted_header() {
+        let journal = MemFile::default();
+        journal
+            .write_all_at(&(1u64 << 46).to_le_bytes(), 0)
+            .unwrap();
+        journal.write_all_at(&[0; 64], HEADER_LEN).unwrap();
+        let err = Journaled::open(MemFile::default(), journal).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{lru::Lru, RandomAccessFile, ReadAt, Size};
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A concatenation of files, which are only opened when read from.
+///
+/// Members are described by their path and size, typically from a manifest,
+/// and are laid out one after the other. Their sizes are trusted: a member
+/// shorter than its declared size causes short reads, and extra bytes in a
+/// longer member are never read.
+///
+/// Open files are kept in a least-recently-used cache: when it is full,
+/// opening a member closes the member that was read from the longest time
+/// ago. A file that is being read from stays open until the read ends, so up
+/// to one more file per concurrent reader may be open.
+pub struct LazyChain {
+    paths: Vec<PathBuf>,
+    /// The offset at which each member starts, followed by the total size.
+    starts: Vec<u64>,
+    files: Mutex<Lru<usize, Arc<RandomAccessFile>>>,
+}
+
+impl LazyChain {
+    /// Creates a new `LazyChain` from a list of paths and sizes, keeping at
+    /// most `max_open` files open.
+    ///
+    /// No file is opened by this function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_open` is zero or if the total size overflows a `u64`.
+    pub fn new<I, P>(members: I, max_open: usize) -> Self
+    where
+        I: IntoIterator<Item = (P, u64)>,
+        P: Into<PathBuf>,
+    {
+        let mut paths = Vec::new();
+        let mut starts = vec![0];
+        let mut end = 0u64;
+        for (path, size) in members {
+            end = end.checked_add(size).expect("total size overflow");
+            paths.push(path.into());
+            starts.push(end);
+        }
+
+        Self {
+            paths,
+            starts,
+            files: Mutex::new(Lru::new(max_open)),
+        }
+    }
+
+    /// Creates a new `LazyChain` from a manifest file, keeping at most
+    /// `max_open` files open.
+    ///
+    /// The manifest is a UTF-8 text file listing one member per line, as its
+    /// path followed by its size in bytes, separated by whitespace. The size
+    /// is the last field of the line, so paths may contain spaces. Relative
+    /// paths are relative to the directory of the manifest. Empty lines and
+    /// lines starting with `#` are ignored:
+    ///
+    /// ```text
+    /// # Dataset, in two parts
+    /// part-1.bin 1048576
+    /// part 2.bin 524288
+    /// ```
+    ///
+    /// Members are not opened by this function, so sizes are trusted as with
+    /// [`new`](Self::new).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a line is
+    /// malformed or if the total size overflows a `u64`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_open` is zero.
+    pub fn from_manifest<P: AsRef<Path>>(manifest_path: P, max_open: usize) -> io::Result<Self> {
+        let manifest_path = manifest_path.as_ref();
+        let manifest = std::fs::read_to_string(manifest_path)?;
+        let dir = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut members = Vec::new();
+        let mut total = 0u64;
+        for (index, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid_line = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid manifest entry on line {}", index + 1),
+                )
+            };
+            let (path, size) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(invalid_line)?;
+            let size: u64 = size.parse().map_err(|_| invalid_line())?;
+            total = total.checked_add(size).ok_or_else(invalid_line)?;
+            members.push((dir.join(path.trim_end()), size));
+        }
+
+        Ok(Self::new(members, max_open))
+    }
+
+    /// Returns the number of members.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns `true` if there are no members.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Returns the number of files currently kept open.
+    #[must_use]
+    pub fn open_files(&self) -> usize {
+        self.files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn total_size(&self) -> u64 {
+        *self.starts.last().unwrap()
+    }
+
+    fn member(&self, index: usize) -> io::Result<Arc<RandomAccessFile>> {
+        let mut files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(file) = files.get(&index) {
+            return Ok(file.clone());
+        }
+
+        let file = Arc::new(RandomAccessFile::open(&self.paths[index])?);
+        files.insert(index, file.clone());
+        Ok(file)
+    }
+}
+
+impl ReadAt for LazyChain {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.total_size() || buf.is_empty() {
+            return Ok(0);
+        }
+
+        // The last member starting at or before `offset`, which skips empty
+        // members.
+        let index = self.starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.starts[index];
+        let len = (self.starts[index + 1] - offset).min(buf.len() as u64) as usize;
+
+        let file = self.member(index)?;
+        file.read_at(&mut buf[..len], offset - start)
+    }
+}
+
+impl Size for LazyChain {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.total_size())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.total_size())
+    }
+}
+
+impl fmt::Debug for LazyChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyChain")
+            .field("paths", &self.paths)
+            .field("size", &self.total_size())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+
+    #[test]
+    fn bounded_open_files() {
+        let contents: [&[u8]; 5] = [b"Hello", b" ", b"", b"World", b"!"];
+        let paths: Vec<_> = (0..contents.len())
+            .map(|i| TempPath::new(&format!("lazy_chain_{i}")))
+            .collect();
+        for (path, data) in paths.iter().zip(&contents) {
+            std::fs::write(path, data).unwrap();
+        }
+
+        let chain = LazyChain::new(
+            paths
+                .iter()
+                .zip(&contents)
+                .map(|(path, data)| (path.as_ref(), data.len() as u64)),
+            2,
+        );
+        assert_eq!(chain.size().unwrap(), 12);
+        assert_eq!(chain.open_files(), 0);
+
+        let mut buf = [0; 12];
+        chain.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello World!");
+        assert_eq!(chain.open_files(), 2);
+
+        for &offset in &[11, 0, 6, 5, 3, 8] {
+            chain.read_exact_at(&mut buf[..1], offset).unwrap();
+            assert_eq!(buf[0], b"Hello World!"[offset as usize]);
+            assert!(chain.open_files() <= 2);
+        }
+        assert_eq!(chain.read_at(&mut buf, 12).unwrap(), 0);
+    }
+
+    #[test]
+    fn from_manifest() {
+        let first = TempPath::new("lazy_chain_manifest_first");
+        let second = TempPath::new("lazy chain manifest second");
+        let manifest = TempPath::new("lazy_chain_manifest");
+        std::fs::write(&first, b"Hello ").unwrap();
+        std::fs::write(&second, b"World!").unwrap();
+
+        let name = |path: &TempPath| {
+            let path: &Path = path.as_ref();
+            path.file_name().unwrap().to_str().unwrap().to_owned()
+        };
+        let contents = format!(
+            "# Test manifest\n\n{} 6\n{}  6\n",
+            name(&first),
+            name(&second)
+        );
+        std::fs::write(&manifest, contents).unwrap();
+
+        let chain = LazyChain::from_manifest(&manifest, 1).unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.size().unwrap(), 12);
+
+        let mut buf = [0; 6];
+        chain.read_exact_at(&mut buf, 3).unwrap();
+        assert_eq!(&buf, b"lo Wor");
+
+        std::fs::write(&manifest, "missing-size\n").unwrap();
+        let err = LazyChain::from_manifest(&manifest, 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
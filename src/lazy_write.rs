@@ -0,0 +1,190 @@
+use crate::{RandomAccessFile, ReadAt, Size, WriteAt};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{PoisonError, RwLock},
+};
+
+/// A file opened for reading, which is only opened for writing when written
+/// to.
+///
+/// This saves writable file descriptors for read-mostly workloads: reads use
+/// a read-only descriptor, and the first write reopens the file by path with
+/// write access. The writable descriptor is then kept for later writes.
+///
+/// If the file cannot be opened for writing, for example because of its
+/// permissions, the first write fails with the error of the reopening, and
+/// the next write tries again. On Unix, the reopened file must be the same
+/// file as the one open for reading: if the path was replaced in the
+/// meantime, writes fail with [`io::ErrorKind::NotFound`].
+#[derive(Debug)]
+pub struct LazyWriteFile {
+    path: PathBuf,
+    reader: RandomAccessFile,
+    writer: RwLock<Option<RandomAccessFile>>,
+}
+
+impl LazyWriteFile {
+    /// Opens a file in read-only mode.
+    ///
+    /// The file is not opened for writing until it is written to.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        Ok(Self {
+            reader: RandomAccessFile::open(path)?,
+            path: path.to_owned(),
+            writer: RwLock::new(None),
+        })
+    }
+
+    /// Returns the path of the file.
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `true` if the file was opened for writing.
+    #[must_use]
+    pub fn is_open_for_writing(&self) -> bool {
+        self.writer
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    /// Calls `f` with the writable file, opening it if needed.
+    fn with_writer<T>(&self, f: impl FnOnce(&RandomAccessFile) -> io::Result<T>) -> io::Result<T> {
+        {
+            let writer = self.writer.read().unwrap_or_else(PoisonError::into_inner);
+            if let Some(writer) = &*writer {
+                return f(writer);
+            }
+        }
+
+        let mut writer = self.writer.write().unwrap_or_else(PoisonError::into_inner);
+        if writer.is_none() {
+            *writer = Some(self.open_writer()?);
+        }
+        f(writer.as_ref().unwrap())
+    }
+
+    fn open_writer(&self) -> io::Result<RandomAccessFile> {
+        let file = fs::OpenOptions::new().write(true).open(&self.path)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let (old, new) = (self.reader.metadata()?, file.metadata()?);
+            if (old.dev(), old.ino()) != (new.dev(), new.ino()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "the file was replaced since it was opened",
+                ));
+            }
+        }
+
+        Ok(RandomAccessFile::from(file))
+    }
+}
+
+impl ReadAt for LazyWriteFile {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.reader.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.reader.read_exact_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        self.reader.read_vectored_at(bufs, offset)
+    }
+}
+
+impl WriteAt for LazyWriteFile {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.with_writer(|f| f.write_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.with_writer(|f| f.write_all_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        self.with_writer(|f| f.write_vectored_at(bufs, offset))
+    }
+
+    /// Flushes the writable file, if it was opened.
+    fn flush(&self) -> io::Result<()> {
+        let writer = self.writer.read().unwrap_or_else(PoisonError::into_inner);
+        match &*writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Syncs the writable file, if it was opened.
+    ///
+    /// Otherwise nothing was written through this file, and its length is
+    /// returned.
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let writer = self.writer.read().unwrap_or_else(PoisonError::into_inner);
+        match &*writer {
+            Some(writer) => writer.flush_tracked(),
+            None => self.reader.len(),
+        }
+    }
+}
+
+impl Size for LazyWriteFile {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.reader.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+
+    #[test]
+    fn opens_on_first_write() {
+        let path = TempPath::new("lazy_write");
+        fs::write(&path, b"Hello World!").unwrap();
+
+        let file = LazyWriteFile::open(&path).unwrap();
+        let mut buf = [0; 5];
+        file.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello");
+        file.flush().unwrap();
+        assert!(!file.is_open_for_writing());
+
+        file.write_all_at(b"Rust!", 6).unwrap();
+        assert!(file.is_open_for_writing());
+        file.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"Rust!");
+    }
+
+    #[test]
+    fn error_on_first_write() {
+        let path = TempPath::new("lazy_write_removed");
+        fs::write(&path, b"Hello World!").unwrap();
+
+        let file = LazyWriteFile::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let err = file.write_all_at(b"Rust!", 6).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(!file.is_open_for_writing());
+        assert_eq!(file.size().unwrap(), 12);
+    }
+}
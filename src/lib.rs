@@ -64,11 +64,124 @@
 #![warn(missing_docs)]
 
 mod adapter;
+mod align;
+#[cfg(feature = "tar")]
+mod archive;
+mod arena;
+mod bits;
+mod block_device;
+mod byte_swap;
+mod cached_metadata;
+mod cas;
+mod checksummed;
+#[cfg(feature = "flate2")]
+mod compressed;
+mod crc32;
+mod dedup;
+mod fault;
 mod file;
-
-pub use adapter::Adapter;
-pub use file::{RandomAccessFile, SyncFile};
-
+mod follow;
+#[cfg(feature = "bytemuck")]
+mod framed;
+mod hashing;
+mod indexed_log;
+mod inspect;
+mod journal;
+mod lazy_chain;
+mod lazy_write;
+mod lock;
+mod lru;
+mod memoize;
+#[cfg(feature = "memmap2")]
+mod mmap;
+mod observable;
+mod offset;
+mod pipeline;
+#[cfg(feature = "bytemuck")]
+mod pod;
+mod pool;
+mod quota;
+mod range_map;
+mod range_set;
+mod rate_limit;
+mod read_only;
+mod readahead;
+mod recording;
+mod reordering;
+mod resumable;
+mod retry;
+#[cfg(feature = "chacha20poly1305")]
+mod sealed;
+mod seek_read;
+mod space_recovering;
+mod sparse;
+mod staged;
+mod striped;
+mod tee;
+mod tiered;
+mod timed;
+mod updatable;
+mod view;
+mod wide;
+
+pub use adapter::{Adapter, FixedReader};
+pub use align::AutoAlign;
+#[cfg(feature = "tar")]
+pub use archive::{tar_entries, ArchiveMember, TarEntry};
+pub use arena::Arena;
+pub use bits::{BitOrder, BitReader, BitWriter};
+pub use block_device::BlockDevice;
+pub use byte_swap::ByteSwapped;
+pub use cached_metadata::CachedMetadata;
+pub use cas::CasReader;
+pub use checksummed::ChecksummedBlocks;
+#[cfg(feature = "flate2")]
+pub use compressed::{CompressedWriter, Decompressed};
+pub use dedup::DedupWriter;
+pub use fault::FaultInjecting;
+pub use file::{sync_all_ordered, Capabilities, RandomAccessFile, ReadHalf, SyncFile, WriteHalf};
+pub use follow::FollowIter;
+#[cfg(feature = "bytemuck")]
+pub use framed::Framed;
+pub use hashing::HashingReader;
+pub use indexed_log::IndexedLog;
+pub use inspect::{InspectRead, InspectWrite};
+pub use journal::{Journaled, Transaction};
+pub use lazy_chain::LazyChain;
+pub use lazy_write::LazyWriteFile;
+pub use memoize::Memoize;
+#[cfg(feature = "memmap2")]
+pub use mmap::{MmapFile, MmapFileMut};
+pub use observable::Observable;
+pub use offset::OffsetReader;
+pub use pipeline::Pipeline;
+#[cfg(feature = "bytemuck")]
+pub use pod::StructIter;
+pub use pool::{BufferPool, PooledBuf};
+pub use quota::Quota;
+pub use range_set::RangeSet;
+pub use rate_limit::RateLimited;
+pub use read_only::ReadOnly;
+pub use readahead::ReadaheadFile;
+pub use recording::{RecordingWriter, WriteOp};
+pub use reordering::Reordering;
+pub use resumable::ResumableWriter;
+pub use retry::RetryInterrupted;
+#[cfg(feature = "chacha20poly1305")]
+pub use sealed::{SealedReader, SealedWriter};
+pub use seek_read::SeekReadAt;
+pub use space_recovering::SpaceRecovering;
+pub use sparse::sparse_copy;
+pub use staged::Staged;
+pub use striped::Striped;
+pub use tee::TeeWriter;
+pub use tiered::TieredBuffer;
+pub use timed::{LatencyPercentiles, Timed};
+pub use updatable::Updatable;
+pub use view::View;
+pub use wide::{ReadAtWide, WideAdapter};
+
+use crate::crc32::Crc32;
 use std::{cmp::min, convert::TryInto, io};
 
 /// The `ReadAt` trait allows for reading bytes from a source at a given offset.
@@ -100,23 +213,83 @@ pub trait ReadAt {
     ///
     /// If any other read error is encountered then this function immediately
     /// returns. The contents of buf are unspecified in this case.
-    fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
-        while !buf.is_empty() {
-            match self.read_at(buf, offset) {
-                Ok(0) => break,
-                Ok(n) => {
-                    buf = &mut buf[n..];
-                    offset += n as u64;
-                }
-                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        default_read_exact_at(self, buf, offset)
+    }
+
+    /// Reads exactly `N` bytes from the given offset into an array.
+    ///
+    /// # Errors
+    ///
+    /// This function has the same error semantics as
+    /// [`read_exact_at`](Self::read_exact_at).
+    #[inline]
+    fn read_array_at<const N: usize>(&self, offset: u64) -> io::Result<[u8; N]>
+    where
+        Self: Sized,
+    {
+        let mut buf = [0; N];
+        self.read_exact_at(&mut buf, offset)?;
+        Ok(buf)
+    }
+
+    /// Reads bytes from the given offset until `sentinel` or the end of the
+    /// source is reached, appending them to `buf`.
+    ///
+    /// Unlike [`io::BufRead::read_until`], the sentinel is not appended to
+    /// `buf`, but it is counted in the returned number of bytes consumed, so
+    /// that the next field starts at `offset` plus that number.
+    ///
+    /// # Errors
+    ///
+    /// If this function encounters an error of the kind
+    /// [`io::ErrorKind::Interrupted`] then the error is ignored and the
+    /// operation will continue. If any other read error is encountered then
+    /// this function immediately returns. Bytes read until then are appended
+    /// to `buf`.
+    fn read_until_at(&self, offset: u64, sentinel: u8, buf: &mut Vec<u8>) -> io::Result<u64> {
+        let mut chunk = [0; 256];
+        let mut consumed = 0;
+
+        loop {
+            let n = match self.read_at(&mut chunk, offset + consumed) {
+                Ok(0) => return Ok(consumed),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
                 Err(e) => return Err(e),
+            };
+
+            match chunk[..n].iter().position(|&b| b == sentinel) {
+                Some(pos) => {
+                    buf.extend_from_slice(&chunk[..pos]);
+                    return Ok(consumed + pos as u64 + 1);
+                }
+                None => {
+                    buf.extend_from_slice(&chunk[..n]);
+                    consumed += n as u64;
+                }
             }
         }
-        if buf.is_empty() {
-            Ok(())
-        } else {
-            Err(fill_buffer_error())
-        }
+    }
+
+    /// Reads exactly `N` bytes from the given offset into an array, without
+    /// initializing it first where possible.
+    ///
+    /// This is equivalent to [`read_array_at`](Self::read_array_at), which
+    /// the default implementation calls. Sources that can read into
+    /// uninitialized memory override it to skip zeroing the array:
+    /// [`RandomAccessFile`] does so on Unix.
+    ///
+    /// # Errors
+    ///
+    /// This function has the same error semantics as
+    /// [`read_exact_at`](Self::read_exact_at).
+    #[inline]
+    fn read_uninit_array_at<const N: usize>(&self, offset: u64) -> io::Result<[u8; N]>
+    where
+        Self: Sized,
+    {
+        self.read_array_at(offset)
     }
 
     /// Like `read_at`, except that it reads into a slice of buffers.
@@ -131,6 +304,211 @@ pub trait ReadAt {
             .map_or(&mut [][..], |b| &mut **b);
         self.read_at(buf, offset)
     }
+
+    /// Reads several ranges, filling each buffer entirely.
+    ///
+    /// Requests are sorted by offset, and contiguous ones are read with a
+    /// single call to [`read_vectored_at`](Self::read_vectored_at), so that
+    /// fewer calls are made than reading each range separately. Short reads are
+    /// then completed with [`read_exact_at`](Self::read_exact_at).
+    ///
+    /// # Errors
+    ///
+    /// This function has the same error semantics as `read_exact_at`. The
+    /// contents of the buffers are unspecified if an error is returned.
+    fn read_ranges(&self, requests: &mut [(u64, &mut [u8])]) -> io::Result<()> {
+        requests.sort_unstable_by_key(|(offset, _)| *offset);
+
+        let mut rest = requests;
+        while let Some((&mut (offset, ref first), _)) = rest.split_first_mut() {
+            let mut end = offset.checked_add(first.len() as u64);
+            let mut len = 1;
+            while len < rest.len() && Some(rest[len].0) == end {
+                end = end.and_then(|end| end.checked_add(rest[len].1.len() as u64));
+                len += 1;
+            }
+
+            let (group, tail) = std::mem::take(&mut rest).split_at_mut(len);
+            rest = tail;
+
+            let mut bufs: Vec<_> = group
+                .iter_mut()
+                .map(|(_, buf)| io::IoSliceMut::new(buf))
+                .collect();
+            let mut read = loop {
+                match self.read_vectored_at(&mut bufs, offset) {
+                    Ok(n) => break n,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            };
+            drop(bufs);
+
+            for (offset, buf) in group {
+                if read >= buf.len() {
+                    read -= buf.len();
+                } else {
+                    self.read_exact_at(&mut buf[read..], *offset + read as u64)?;
+                    read = 0;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the value at `index` in an array of `T` stored from offset 0.
+    ///
+    /// This reads `size_of::<T>()` bytes at offset `index * size_of::<T>()`.
+    /// Values are read as they are laid out in memory, so they are
+    /// native-endian.
+    #[cfg(feature = "bytemuck")]
+    fn read_struct_at<T: bytemuck::Pod>(&self, index: u64) -> io::Result<T>
+    where
+        Self: Sized,
+    {
+        pod::read_struct_at(self, index)
+    }
+
+    /// Returns an iterator over the first `count` values of an array of `T`
+    /// stored from offset 0.
+    ///
+    /// See [`read_struct_at`](Self::read_struct_at) for details.
+    #[cfg(feature = "bytemuck")]
+    fn struct_iter<T: bytemuck::Pod>(&self, count: u64) -> StructIter<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        StructIter::new(self, count)
+    }
+
+    /// Reads up to `len` bytes at `offset` into a buffer taken from `pool`.
+    ///
+    /// As with [`read_at`](Self::read_at), the returned buffer may hold fewer
+    /// than `len` bytes. It goes back to the pool when dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if `len` is
+    /// greater than the size of the buffers of the pool.
+    fn read_at_pooled<'p>(
+        &self,
+        len: usize,
+        offset: u64,
+        pool: &'p BufferPool,
+    ) -> io::Result<PooledBuf<'p>> {
+        if len > pool.buffer_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "read larger than pooled buffers",
+            ));
+        }
+
+        let mut buf = pool.get();
+        buf.truncate(len);
+        let read = self.read_at(&mut buf, offset)?;
+        buf.truncate(read);
+        Ok(buf)
+    }
+
+    /// Creates a reader that always reads from the given offset.
+    ///
+    /// The returned reader **does not advance**: each call to `read` reads
+    /// from `offset` again, which is useful to retry reads. See
+    /// [`FixedReader`] for details.
+    #[inline]
+    fn reader_fixed(&self, offset: u64) -> FixedReader<'_, Self>
+    where
+        Self: Sized,
+    {
+        FixedReader::new(self, offset)
+    }
+
+    /// Creates a reader that calls `f` after each successful read, with its
+    /// offset and the bytes read.
+    ///
+    /// Reads are otherwise passed through unchanged, which is useful to log
+    /// access patterns. `f` is called through a shared reference, so it must
+    /// use interior mutability to record calls, and be `Sync` for the reader
+    /// to be shared between threads.
+    #[inline]
+    fn inspect_reads<F>(self, f: F) -> InspectRead<Self, F>
+    where
+        Self: Sized,
+        F: Fn(u64, &[u8]),
+    {
+        InspectRead::new(self, f)
+    }
+
+    /// Checks that the source starts with `magic`, and returns a reader for
+    /// the bytes after it.
+    ///
+    /// Offsets of the returned reader are relative to the end of the magic,
+    /// so that format loaders can read the payload without accounting for
+    /// the preamble.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the source
+    /// does not start with `magic`, including if it is shorter than it.
+    fn skip_preamble(self, magic: &[u8]) -> io::Result<OffsetReader<Self>>
+    where
+        Self: Sized,
+    {
+        let mut preamble = vec![0; magic.len()];
+        match self.read_exact_at(&mut preamble, 0) {
+            Ok(()) if preamble == magic => Ok(OffsetReader::new(self, magic.len() as u64)),
+            Err(e) if e.kind() != io::ErrorKind::UnexpectedEof => Err(e),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "wrong magic")),
+        }
+    }
+
+    /// Checks the CRC-32 stored in the trailer of the source.
+    ///
+    /// The trailer is made of the last `footer_len` bytes of the source, and
+    /// starts with the CRC-32 of all the bytes before it, as a little-endian
+    /// `u32`. Returns whether the computed checksum matches the stored one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if
+    /// `footer_len` is less than 4, and of kind
+    /// [`io::ErrorKind::UnexpectedEof`] if the source is smaller than the
+    /// footer.
+    fn verify_trailer_crc32(&self, footer_len: usize) -> io::Result<bool>
+    where
+        Self: Size + Sized,
+    {
+        if footer_len < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "footer too small to hold a CRC-32",
+            ));
+        }
+        let size = self.size()?;
+        let payload_len = size
+            .checked_sub(footer_len as u64)
+            .ok_or_else(|| out_of_range_error(0, footer_len, size))?;
+
+        let expected = u32::from_le_bytes(self.read_array_at(payload_len)?);
+
+        let mut crc = Crc32::new();
+        let mut chunk = [0; 8192];
+        let mut offset = 0;
+        while offset < payload_len {
+            let len = (payload_len - offset).min(chunk.len() as u64) as usize;
+            let n = match self.read_at(&mut chunk[..len], offset) {
+                Ok(0) => return Err(fill_buffer_error()),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            crc.update(&chunk[..n]);
+            offset += n as u64;
+        }
+
+        Ok(crc.finish() == expected)
+    }
 }
 
 impl ReadAt for [u8] {
@@ -156,7 +534,20 @@ impl ReadAt for [u8] {
             let len = buf.len();
             (this.len() >= len).then(|| buf.copy_from_slice(&this[..len]))
         })()
-        .ok_or_else(fill_buffer_error)
+        .ok_or_else(|| out_of_range_error(offset, buf.len(), self.len() as u64))
+    }
+
+    #[inline]
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        let mut read = 0;
+        for buf in bufs {
+            let n = self.read_at(buf, offset + read as u64)?;
+            read += n;
+            if n < buf.len() {
+                break;
+            }
+        }
+        Ok(read)
     }
 }
 
@@ -392,6 +783,42 @@ pub trait WriteAt {
     fn flush(&self) -> io::Result<()> {
         Ok(())
     }
+
+    /// Flushes this output stream, and returns the offset up to which the
+    /// written bytes are durable.
+    ///
+    /// For [`RandomAccessFile`] and types wrapping it, this calls
+    /// [`sync_data`](RandomAccessFile::sync_data) and returns the length of
+    /// the file. In-memory writers return their current size, as flushing
+    /// them is a no-op, and [`io::Sink`] returns zero. Wrappers forward the
+    /// call to the writer they wrap, and translate the returned offset if
+    /// their offsets differ from the ones of the inner writer.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation calls [`flush`](Self::flush), then returns
+    /// an error of kind [`io::ErrorKind::Unsupported`], as it cannot know
+    /// which bytes are durable.
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.flush()?;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this writer does not track durable bytes",
+        ))
+    }
+
+    /// Creates a writer that calls `f` after each successful write, with its
+    /// offset and the bytes written.
+    ///
+    /// See [`ReadAt::inspect_reads`] for details.
+    #[inline]
+    fn inspect_writes<F>(self, f: F) -> InspectWrite<Self, F>
+    where
+        Self: Sized,
+        F: Fn(u64, &[u8]),
+    {
+        InspectWrite::new(self, f)
+    }
 }
 
 impl<W> WriteAt for &W
@@ -417,6 +844,11 @@ where
     fn flush(&self) -> io::Result<()> {
         (**self).flush()
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        (**self).flush_tracked()
+    }
 }
 
 impl<W> WriteAt for Box<W>
@@ -442,6 +874,11 @@ where
     fn flush(&self) -> io::Result<()> {
         (**self).flush()
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        (**self).flush_tracked()
+    }
 }
 
 impl<W> WriteAt for std::sync::Arc<W>
@@ -467,6 +904,11 @@ where
     fn flush(&self) -> io::Result<()> {
         (**self).flush()
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        (**self).flush_tracked()
+    }
 }
 
 impl<W> WriteAt for std::rc::Rc<W>
@@ -492,6 +934,11 @@ where
     fn flush(&self) -> io::Result<()> {
         (**self).flush()
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        (**self).flush_tracked()
+    }
 }
 
 impl WriteAt for io::Sink {
@@ -509,6 +956,215 @@ impl WriteAt for io::Sink {
     fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], _offset: u64) -> io::Result<usize> {
         Ok(bufs.iter().map(|b| b.len()).sum())
     }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+/// The `Size` trait allows for querying the size of a source.
+pub trait Size {
+    /// Returns the size of the source, in bytes.
+    fn size(&self) -> io::Result<u64>;
+
+    /// Returns the size of the source, or `None` if it cannot be queried.
+    ///
+    /// Sources whose size is always known, such as in-memory buffers, return
+    /// it directly, which lets generic code optimistically pre-allocate
+    /// buffers without handling errors.
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.size().ok()
+    }
+}
+
+impl Size for [u8] {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+}
+
+impl<const N: usize> Size for [u8; N] {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(N as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(N as u64)
+    }
+}
+
+impl Size for Vec<u8> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+}
+
+impl Size for std::borrow::Cow<'_, [u8]> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+}
+
+impl<S> Size for &S
+where
+    S: Size + ?Sized,
+{
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        (**self).size_hint()
+    }
+}
+
+impl<S> Size for Box<S>
+where
+    S: Size + ?Sized,
+{
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        (**self).size_hint()
+    }
+}
+
+impl<S> Size for std::sync::Arc<S>
+where
+    S: Size + ?Sized,
+{
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        (**self).size_hint()
+    }
+}
+
+impl<S> Size for std::rc::Rc<S>
+where
+    S: Size + ?Sized,
+{
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        (**self).size_hint()
+    }
+}
+
+impl<T> Size for io::Cursor<T>
+where
+    T: AsRef<[u8]>,
+{
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.get_ref().as_ref().len() as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.get_ref().as_ref().len() as u64)
+    }
+}
+
+impl Size for io::Empty {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(0)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(0)
+    }
+}
+
+/// The `TrySize` trait allows for querying the size of a source that may be
+/// unbounded.
+///
+/// Sources with no meaningful size, such as a log that keeps growing while it
+/// is read, return `None`. All [`Size`] implementors implement this trait and
+/// return their size, so generic code can accept both kinds of sources.
+pub trait TrySize {
+    /// Returns the size of the source in bytes, or `None` if it is unbounded.
+    fn try_size(&self) -> io::Result<Option<u64>>;
+}
+
+impl<S: Size + ?Sized> TrySize for S {
+    #[inline]
+    fn try_size(&self) -> io::Result<Option<u64>> {
+        self.size().map(Some)
+    }
+}
+
+/// Returns whether two sources have the same contents.
+///
+/// Sizes are compared first, so sources of different sizes are not read at
+/// all. Otherwise, both sources are read in chunks until the first difference.
+pub fn contents_eq<A, B>(a: &A, b: &B) -> io::Result<bool>
+where
+    A: ReadAt + Size + ?Sized,
+    B: ReadAt + Size + ?Sized,
+{
+    const CHUNK_SIZE: u64 = 8 * 1024;
+
+    let size = a.size()?;
+    if size != b.size()? {
+        return Ok(false);
+    }
+
+    let buf_len = min(size, CHUNK_SIZE) as usize;
+    let mut buf_a = vec![0; buf_len];
+    let mut buf_b = vec![0; buf_len];
+
+    let mut offset = 0;
+    while offset < size {
+        let len = min(size - offset, CHUNK_SIZE) as usize;
+        let (chunk_a, chunk_b) = (&mut buf_a[..len], &mut buf_b[..len]);
+        a.read_exact_at(chunk_a, offset)?;
+        b.read_exact_at(chunk_b, offset)?;
+        if chunk_a != chunk_b {
+            return Ok(false);
+        }
+        offset += len as u64;
+    }
+
+    Ok(true)
 }
 
 #[cold]
@@ -516,6 +1172,80 @@ fn fill_buffer_error() -> io::Error {
     io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")
 }
 
+/// Fills `buf` from `offset`, stopping early only at the end of the source.
+pub(crate) fn read_full<R: ReadAt + ?Sized>(
+    reader: &R,
+    buf: &mut [u8],
+    offset: u64,
+) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read_at(&mut buf[read..], offset + read as u64) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+/// The default implementation of [`ReadAt::read_exact_at`], usable by
+/// implementations that only override it on some platforms.
+pub(crate) fn default_read_exact_at<R: ReadAt + ?Sized>(
+    reader: &R,
+    mut buf: &mut [u8],
+    mut offset: u64,
+) -> io::Result<()> {
+    while !buf.is_empty() {
+        match reader.read_at(buf, offset) {
+            Ok(0) => break,
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if buf.is_empty() {
+        Ok(())
+    } else {
+        Err(fill_buffer_error())
+    }
+}
+
+#[cold]
+fn out_of_range_error(offset: u64, len: usize, size: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!(
+            "failed to fill whole buffer: cannot read {len} bytes at offset {offset} \
+             from a source of {size} bytes"
+        ),
+    )
+}
+
+/// Replaces a generic end-of-file error with one that includes the requested
+/// range and the size of the source, if the range is out of bounds.
+#[cold]
+pub(crate) fn refine_eof_error<R: Size + ?Sized>(
+    reader: &R,
+    err: io::Error,
+    offset: u64,
+    len: usize,
+) -> io::Error {
+    if err.kind() != io::ErrorKind::UnexpectedEof {
+        return err;
+    }
+    match reader.size() {
+        Ok(size) if offset.saturating_add(len as u64) > size => {
+            out_of_range_error(offset, len, size)
+        }
+        _ => err,
+    }
+}
+
 #[cold]
 fn write_buffer_error() -> io::Error {
     io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")
@@ -524,7 +1254,263 @@ fn write_buffer_error() -> io::Error {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::prelude::*;
+    use std::{
+        io::prelude::*,
+        path::{Path, PathBuf},
+    };
+
+    /// A path in the temporary directory, which is removed on drop.
+    pub(crate) struct TempPath(PathBuf);
+
+    impl TempPath {
+        pub(crate) fn new(name: &str) -> Self {
+            let name = format!("sync_file-{}-{}", std::process::id(), name);
+            Self(std::env::temp_dir().join(name))
+        }
+
+        /// Creates an empty file at this path, opened for reading and writing.
+        pub(crate) fn create_rw(&self) -> std::fs::File {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&self.0)
+                .unwrap()
+        }
+    }
+
+    impl AsRef<Path> for TempPath {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// An in-memory file, used to test adapters over writable storage.
+    #[derive(Debug, Default)]
+    pub(crate) struct MemFile(std::sync::Mutex<Vec<u8>>);
+
+    impl MemFile {
+        pub(crate) fn contents(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl ReadAt for MemFile {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            self.0.lock().unwrap().read_at(buf, offset)
+        }
+    }
+
+    impl Size for MemFile {
+        fn size(&self) -> io::Result<u64> {
+            Ok(self.0.lock().unwrap().len() as u64)
+        }
+    }
+
+    impl WriteAt for MemFile {
+        fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+            let mut contents = self.0.lock().unwrap();
+            let offset = offset as usize;
+            let end = offset + buf.len();
+            if contents.len() < end {
+                contents.resize(end, 0);
+            }
+            contents[offset..end].copy_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush_tracked(&self) -> io::Result<u64> {
+            Ok(self.0.lock().unwrap().len() as u64)
+        }
+    }
+
+    #[allow(dead_code)]
+    fn traits_are_dyn_compatible(_: &dyn ReadAt, _: &dyn WriteAt, _: &dyn Size, _: &dyn TrySize) {}
+
+    #[test]
+    fn read_ranges() {
+        struct Counting(Vec<u8>, std::cell::Cell<usize>);
+
+        impl ReadAt for Counting {
+            fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+                self.1.set(self.1.get() + 1);
+                self.0.read_at(buf, offset)
+            }
+
+            fn read_vectored_at(
+                &self,
+                bufs: &mut [io::IoSliceMut<'_>],
+                offset: u64,
+            ) -> io::Result<usize> {
+                self.1.set(self.1.get() + 1);
+                self.0.read_vectored_at(bufs, offset)
+            }
+        }
+
+        let data = Counting((0..=255).collect(), Default::default());
+        let (mut a, mut b, mut c) = ([0; 4], [0; 2], [0; 3]);
+        let mut requests = [(100, &mut c[..]), (4, &mut b[..]), (0, &mut a[..])];
+        data.read_ranges(&mut requests).unwrap();
+
+        assert_eq!(a, [0, 1, 2, 3]);
+        assert_eq!(b, [4, 5]);
+        assert_eq!(c, [100, 101, 102]);
+        assert_eq!(data.1.get(), 2);
+
+        let mut d = [0; 4];
+        let err = data.read_ranges(&mut [(254, &mut d[..])]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn size_hint() {
+        assert_eq!(b"abc"[..].size_hint(), Some(3));
+        assert_eq!(vec![0u8; 5].size_hint(), Some(5));
+        assert_eq!(io::Cursor::new([0u8; 7]).size_hint(), Some(7));
+        assert_eq!(io::empty().size_hint(), Some(0));
+
+        let len = std::fs::metadata("LICENSE-APACHE").unwrap().len();
+        let f = SyncFile::open("LICENSE-APACHE").unwrap();
+        assert_eq!(f.size_hint(), Some(len));
+        assert_eq!(f.size().unwrap(), len);
+    }
+
+    #[test]
+    fn contents_eq() {
+        struct Unreadable;
+
+        impl ReadAt for Unreadable {
+            fn read_at(&self, _: &mut [u8], _: u64) -> io::Result<usize> {
+                panic!("sizes should be compared first")
+            }
+        }
+
+        impl Size for Unreadable {
+            fn size(&self) -> io::Result<u64> {
+                Ok(4)
+            }
+        }
+
+        assert!(super::contents_eq(b"abcd", b"abcd").unwrap());
+        assert!(!super::contents_eq(b"abcd", b"abce").unwrap());
+        assert!(!super::contents_eq(b"abc", &Unreadable).unwrap());
+
+        let contents = std::fs::read("LICENSE-APACHE").unwrap();
+        let f = SyncFile::open("LICENSE-APACHE").unwrap();
+        assert!(super::contents_eq(&f, &contents).unwrap());
+        let mut other = contents.clone();
+        *other.last_mut().unwrap() ^= 1;
+        assert!(!super::contents_eq(&f, &other).unwrap());
+    }
+
+    #[test]
+    fn try_size() {
+        struct Unbounded;
+
+        impl TrySize for Unbounded {
+            fn try_size(&self) -> io::Result<Option<u64>> {
+                Ok(None)
+            }
+        }
+
+        fn describe<S: TrySize + ?Sized>(source: &S) -> String {
+            match source.try_size().unwrap() {
+                Some(size) => format!("{size} bytes"),
+                None => "unbounded".to_owned(),
+            }
+        }
+
+        assert_eq!(describe(&b"Hello"[..]), "5 bytes");
+        assert_eq!(describe(&Unbounded), "unbounded");
+    }
+
+    #[test]
+    fn read_array_at() {
+        let file = RandomAccessFile::open("LICENSE-MIT").unwrap();
+        assert_eq!(&file.read_array_at::<4>(0).unwrap(), b"Copy");
+
+        let len = file.size().unwrap();
+        let err = file.read_array_at::<4>(len - 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_uninit_array_at() {
+        let file = RandomAccessFile::open("LICENSE-APACHE").unwrap();
+        assert_eq!(
+            &file.read_uninit_array_at::<16>(0).unwrap(),
+            b"Copyright 2021 B"
+        );
+
+        let uninit = file.read_uninit_array_at::<16>(100).unwrap();
+        assert_eq!(uninit, file.read_array_at::<16>(100).unwrap());
+
+        let len = file.size().unwrap();
+        let err = file.read_uninit_array_at::<16>(len - 8).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_until_at() {
+        let mut data = b"name\0".to_vec();
+        data.extend(std::iter::repeat(b'x').take(300));
+        data.extend_from_slice(b"\0tail");
+
+        let mut buf = Vec::new();
+        assert_eq!(data.read_until_at(0, 0, &mut buf).unwrap(), 5);
+        assert_eq!(buf, b"name");
+
+        buf.clear();
+        assert_eq!(data.read_until_at(5, 0, &mut buf).unwrap(), 301);
+        assert_eq!(buf, [b'x'; 300]);
+
+        buf.clear();
+        assert_eq!(data.read_until_at(306, 0, &mut buf).unwrap(), 4);
+        assert_eq!(buf, b"tail");
+    }
+
+    #[test]
+    fn verify_trailer_crc32() {
+        let mut data = vec![7; 20_000];
+        let checksum = crc32::crc32(&data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+        data.extend_from_slice(b"END");
+        assert!(data.verify_trailer_crc32(7).unwrap());
+
+        data[10_000] = 0;
+        assert!(!data.verify_trailer_crc32(7).unwrap());
+
+        let err = b"abc".verify_trailer_crc32(4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn out_of_range_error() {
+        let mut buf = [0; 10];
+
+        let err = b"Hello World!"[..].read_exact_at(&mut buf, 5).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let msg = err.to_string();
+        assert!(
+            msg.contains("offset 5") && msg.contains("12 bytes"),
+            "{msg}"
+        );
+
+        let f = RandomAccessFile::open("LICENSE-APACHE").unwrap();
+        let size = f.size().unwrap();
+        let err = f.read_exact_at(&mut buf, size - 5).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let msg = err.to_string();
+        assert!(msg.contains(&format!("offset {}", size - 5)), "{msg}");
+        assert!(msg.contains(&format!("{size} bytes")), "{msg}");
+    }
 
     #[test]
     fn smoke_test() {
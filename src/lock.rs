@@ -0,0 +1,180 @@
+use crate::RandomAccessFile;
+use std::io;
+
+#[cfg(unix)]
+use std::os::unix::prelude::*;
+#[cfg(target_os = "windows")]
+use std::os::windows::prelude::*;
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Shared,
+    Exclusive,
+}
+
+/// Advisory whole-file locks.
+///
+/// Locks are advisory: they only exclude other lock holders, not reads or
+/// writes. They are held by the underlying open file, so they are shared with
+/// handles created with [`try_clone`](RandomAccessFile::try_clone), and
+/// released when all of them are closed. Two files opened separately, even in
+/// the same process, exclude each other.
+///
+/// On Unix, this uses `flock`, and on Windows `LockFileEx`. On other
+/// platforms, these methods return an error of kind
+/// [`io::ErrorKind::Unsupported`].
+impl RandomAccessFile {
+    /// Acquires an exclusive lock on the file, blocking until it is available.
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        self.with_file(|f| lock(f, Mode::Exclusive, true)).map(drop)
+    }
+
+    /// Acquires a shared lock on the file, blocking until it is available.
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.with_file(|f| lock(f, Mode::Shared, true)).map(drop)
+    }
+
+    /// Tries to acquire an exclusive lock on the file without blocking.
+    ///
+    /// Returns `Ok(false)` if another handle holds a lock on the file, and
+    /// `Err` only on genuine failures.
+    pub fn try_lock_exclusive(&self) -> io::Result<bool> {
+        self.with_file(|f| lock(f, Mode::Exclusive, false))
+    }
+
+    /// Tries to acquire a shared lock on the file without blocking.
+    ///
+    /// Returns `Ok(false)` if another handle holds an exclusive lock on the
+    /// file, and `Err` only on genuine failures.
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.with_file(|f| lock(f, Mode::Shared, false))
+    }
+
+    /// Releases the lock held on the file.
+    pub fn unlock(&self) -> io::Result<()> {
+        self.with_file(unlock)
+    }
+}
+
+#[cfg(unix)]
+fn lock(file: &std::fs::File, mode: Mode, block: bool) -> io::Result<bool> {
+    let mut op = match mode {
+        Mode::Shared => libc::LOCK_SH,
+        Mode::Exclusive => libc::LOCK_EX,
+    };
+    if !block {
+        op |= libc::LOCK_NB;
+    }
+
+    if unsafe { libc::flock(file.as_raw_fd(), op) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if !block && err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(unix)]
+fn unlock(file: &std::fs::File) -> io::Result<()> {
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn lock(file: &std::fs::File, mode: Mode, block: bool) -> io::Result<bool> {
+    use windows_sys::Win32::{
+        Foundation::ERROR_LOCK_VIOLATION,
+        Storage::FileSystem::{LockFileEx, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY},
+    };
+
+    let mut flags = match mode {
+        Mode::Shared => 0,
+        Mode::Exclusive => LOCKFILE_EXCLUSIVE_LOCK,
+    };
+    if !block {
+        flags |= LOCKFILE_FAIL_IMMEDIATELY;
+    }
+
+    let ret = unsafe {
+        let mut overlapped = std::mem::zeroed();
+        LockFileEx(
+            file.as_raw_handle() as _,
+            flags,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    if ret != 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if !block && err.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn unlock(file: &std::fs::File) -> io::Result<()> {
+    use windows_sys::Win32::Storage::FileSystem::UnlockFile;
+
+    if unsafe { UnlockFile(file.as_raw_handle() as _, 0, 0, u32::MAX, u32::MAX) } != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn lock(_: &std::fs::File, _: Mode, _: bool) -> io::Result<bool> {
+    Err(unsupported())
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn unlock(_: &std::fs::File) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+#[cold]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "file locking is not supported on this platform",
+    )
+}
+
+#[cfg(all(test, any(unix, target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+
+    #[test]
+    fn try_lock() {
+        let path = TempPath::new("try_lock");
+        let a = RandomAccessFile::from(path.create_rw());
+        let b = RandomAccessFile::open(&path).unwrap();
+
+        a.lock_exclusive().unwrap();
+        assert!(!b.try_lock_exclusive().unwrap());
+        assert!(!b.try_lock_shared().unwrap());
+
+        a.unlock().unwrap();
+        assert!(b.try_lock_shared().unwrap());
+        assert!(a.try_lock_shared().unwrap());
+        assert!(!a.try_lock_exclusive().unwrap());
+
+        b.unlock().unwrap();
+        a.unlock().unwrap();
+        assert!(a.try_lock_exclusive().unwrap());
+    }
+}
@@ -0,0 +1,98 @@
+//! A small least-recently-used cache.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+
+/// A map with a bounded number of entries, which evicts the least recently
+/// used one when full.
+#[derive(Debug)]
+pub(crate) struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone, V> Lru<K, V> {
+    /// Creates a new cache holding up to `capacity` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub(crate) fn new(capacity: usize) -> Self {
+        assert!(capacity != 0, "capacity must be non-zero");
+
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Gets an entry, marking it as the most recently used.
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        let (value, tick) = self.entries.get_mut(key)?;
+        self.order.remove(tick);
+        self.tick += 1;
+        *tick = self.tick;
+        self.order.insert(self.tick, key.clone());
+        Some(value)
+    }
+
+    /// Inserts an entry as the most recently used, and returns the entry that
+    /// was evicted to make room for it, if any.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.tick += 1;
+        if let Some((old, tick)) = self.entries.get_mut(&key) {
+            *old = value;
+            self.order.remove(tick);
+            *tick = self.tick;
+            self.order.insert(self.tick, key);
+            return None;
+        }
+
+        let evicted = if self.entries.len() == self.capacity {
+            self.pop_oldest()
+        } else {
+            None
+        };
+
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+        evicted
+    }
+
+    /// Removes the least recently used entry.
+    fn pop_oldest(&mut self) -> Option<(K, V)> {
+        let oldest = *self.order.keys().next()?;
+        let key = self.order.remove(&oldest)?;
+        let (value, _) = self.entries.remove(&key)?;
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        assert_eq!(lru.insert(1, "a"), None);
+        assert_eq!(lru.insert(2, "b"), None);
+        assert_eq!(lru.get(&1), Some(&"a"));
+        assert_eq!(lru.insert(3, "c"), Some((2, "b")));
+        assert_eq!(lru.insert(1, "d"), None);
+        assert_eq!(lru.insert(4, "e"), Some((3, "c")));
+        assert_eq!(lru.get(&1), Some(&"d"));
+        assert_eq!(lru.len(), 2);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{lru::Lru, ReadAt, Size};
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Cached reads, by offset and length.
+type Cache = Lru<(u64, usize), Arc<[u8]>>;
+
+/// A reader that memoizes the results of identical reads.
+///
+/// Reads are cached by their offset and length: a read with the same offset
+/// and length as a cached one is served from the cache, and any other read
+/// is a miss, even if its range is covered by cached reads. This is suited to
+/// workloads reading the same regions again and again, such as dictionary
+/// pages, without the cost of a page cache.
+///
+/// The cache holds the results of up to `capacity` distinct reads, and evicts
+/// the least recently used one when full. Cached data is never invalidated,
+/// so the inner reader must not change.
+pub struct Memoize<R> {
+    inner: R,
+    cache: Mutex<Cache>,
+}
+
+impl<R> Memoize<R> {
+    /// Creates a new `Memoize` reader caching up to `capacity` reads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(inner: R, capacity: usize) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Returns the number of reads currently cached.
+    #[must_use]
+    pub fn cached_reads(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn get(&self, offset: u64, len: usize) -> Option<Arc<[u8]>> {
+        self.lock().get(&(offset, len)).cloned()
+    }
+
+    fn insert(&self, offset: u64, len: usize, data: &[u8]) {
+        self.lock().insert((offset, len), data.into());
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Cache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<R: ReadAt> ReadAt for Memoize<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(data) = self.get(offset, buf.len()) {
+            buf[..data.len()].copy_from_slice(&data);
+            return Ok(data.len());
+        }
+
+        let n = self.inner.read_at(buf, offset)?;
+        self.insert(offset, buf.len(), &buf[..n]);
+        Ok(n)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        // A short read cached by `read_at` is a miss.
+        if let Some(data) = self.get(offset, buf.len()) {
+            if data.len() == buf.len() {
+                buf.copy_from_slice(&data);
+                return Ok(());
+            }
+        }
+
+        self.inner.read_exact_at(buf, offset)?;
+        self.insert(offset, buf.len(), buf);
+        Ok(())
+    }
+}
+
+impl<R: Size> Size for Memoize<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for Memoize<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memoize")
+            .field("inner", &self.inner)
+            .field("cached_reads", &self.cached_reads())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn memoizes_reads() {
+        let reads = AtomicUsize::new(0);
+        let reader = Memoize::new(
+            (&b"Hello World!"[..]).inspect_reads(|_, _| {
+                reads.fetch_add(1, Ordering::SeqCst);
+            }),
+            2,
+        );
+
+        let mut buf = [0; 5];
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        // Other reads are misses, and evict the least recently used read.
+        reader.read_exact_at(&mut buf[..4], 6).unwrap();
+        assert_eq!(reader.read_at(&mut buf, 9).unwrap(), 3);
+        assert_eq!(reader.read_at(&mut buf, 9).unwrap(), 3);
+        assert_eq!(&buf[..3], b"ld!");
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+        assert_eq!(reader.cached_reads(), 2);
+
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(reads.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::{RandomAccessFile, RangeSet, ReadAt, Size, WriteAt};
+use std::{
+    cmp::min,
+    io,
+    ops::Range,
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// A read-only memory map of a file.
+///
+/// Reads copy bytes from the mapping, which avoids a system call per read
+/// once pages are in memory. Access hints can be given for ranges of the
+/// mapping: [`prefetch`](Self::prefetch) asks the OS to read pages ahead of
+/// use, and [`evict`](Self::evict) to release them after use.
+///
+/// The mapping has the length of the file when it was created: bytes
+/// appended later are not visible.
+#[derive(Debug)]
+pub struct MmapFile {
+    map: memmap2::Mmap,
+}
+
+impl MmapFile {
+    /// Maps `file` in memory.
+    ///
+    /// # Safety
+    ///
+    /// The mapped file must not be truncated while it is mapped, by this
+    /// process or another, as accessing the missing pages would be undefined
+    /// behaviour. Modifications made to it are visible through the mapping.
+    pub unsafe fn map(file: &RandomAccessFile) -> io::Result<Self> {
+        let map = file.with_file(|f| memmap2::Mmap::map(f))?;
+        Ok(Self { map })
+    }
+
+    /// Returns the mapped bytes.
+    #[must_use]
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Returns the length of the mapping.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the mapping is empty.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Hints that `len` bytes at `offset` will be read soon.
+    ///
+    /// This issues `madvise(MADV_WILLNEED)` on Unix, which starts reading
+    /// the pages in the background. On other platforms, this does nothing.
+    /// The range is clamped to the mapping.
+    pub fn prefetch(&self, offset: u64, len: u64) -> io::Result<()> {
+        let range = match self.clamp(offset, len) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        #[cfg(unix)]
+        {
+            self.map
+                .advise_range(memmap2::Advice::WillNeed, range.start, range.len())
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = range;
+            Ok(())
+        }
+    }
+
+    /// Hints that `len` bytes at `offset` will not be read soon.
+    ///
+    /// This issues `madvise(MADV_DONTNEED)` on Unix, which releases the
+    /// pages: they are read from the file again on the next access. On other
+    /// platforms, this does nothing. The range is clamped to the mapping.
+    pub fn evict(&self, offset: u64, len: u64) -> io::Result<()> {
+        let range = match self.clamp(offset, len) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        #[cfg(unix)]
+        {
+            // SAFETY: the mapping is shared and read-only, so released pages
+            // are read back from the file and no modification is lost.
+            unsafe {
+                self.map.unchecked_advise_range(
+                    memmap2::UncheckedAdvice::DontNeed,
+                    range.start,
+                    range.len(),
+                )
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = range;
+            Ok(())
+        }
+    }
+
+    /// Clamps a range to the mapping, returning `None` if it is empty.
+    fn clamp(&self, offset: u64, len: u64) -> Option<Range<usize>> {
+        let map_len = self.map.len() as u64;
+        let start = min(offset, map_len);
+        let end = min(offset.saturating_add(len), map_len);
+        (start < end).then_some(start as usize..end as usize)
+    }
+}
+
+impl ReadAt for MmapFile {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.as_slice().read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.as_slice().read_exact_at(buf, offset)
+    }
+}
+
+impl Size for MmapFile {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.map.len() as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.map.len() as u64)
+    }
+}
+
+/// A writable memory map of a file.
+///
+/// Writes are plain memory stores into the mapping, and the written ranges
+/// are tracked until they are flushed, so that flushing only synchronizes
+/// dirty pages instead of the whole mapping.
+///
+/// The mapping has the length of the file when it was created: writes past
+/// its end are short, and [`write_all_at`](WriteAt::write_all_at) fails with
+/// [`io::ErrorKind::WriteZero`] there.
+///
+/// # Durability
+///
+/// Written data reaches the file's page cache immediately, and is visible to
+/// other readers of the file, but is only written to storage when the OS
+/// decides to, or when it is flushed. [`flush`](WriteAt::flush) and
+/// [`flush_range`](Self::flush_range) synchronously write back the dirty
+/// pages (`msync(MS_SYNC)` on Unix, `FlushViewOfFile` and
+/// `FlushFileBuffers` on Windows). Unlike `fsync`, `msync` does not write
+/// back metadata or data written through other means than the mapping, which
+/// need [`RandomAccessFile::sync_all`].
+#[derive(Debug)]
+pub struct MmapFileMut {
+    state: RwLock<MutState>,
+}
+
+#[derive(Debug)]
+struct MutState {
+    map: memmap2::MmapMut,
+    dirty: RangeSet,
+}
+
+impl MmapFileMut {
+    /// Maps `file` in memory for reading and writing.
+    ///
+    /// The file must be open for reading and writing.
+    ///
+    /// # Safety
+    ///
+    /// The mapped file must not be truncated while it is mapped, by this
+    /// process or another, as accessing the missing pages would be undefined
+    /// behaviour. It must not be modified by other means either, as this
+    /// would race with accesses through the mapping.
+    pub unsafe fn map_mut(file: &RandomAccessFile) -> io::Result<Self> {
+        let map = file.with_file(|f| memmap2::MmapMut::map_mut(f))?;
+        Ok(Self {
+            state: RwLock::new(MutState {
+                map,
+                dirty: RangeSet::new(),
+            }),
+        })
+    }
+
+    /// Returns the length of the mapping.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.read().map.len()
+    }
+
+    /// Returns `true` if the mapping is empty.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.read().map.is_empty()
+    }
+
+    /// Returns the ranges written since they were last flushed.
+    #[must_use]
+    pub fn dirty_ranges(&self) -> RangeSet {
+        self.read().dirty.clone()
+    }
+
+    /// Synchronously writes back `len` bytes at `offset` to the file.
+    ///
+    /// The range is clamped to the mapping. Other dirty ranges are left
+    /// untouched.
+    pub fn flush_range(&self, offset: u64, len: u64) -> io::Result<()> {
+        let mut state = self.write();
+        let map_len = state.map.len() as u64;
+        let start = min(offset, map_len);
+        let end = min(offset.saturating_add(len), map_len);
+        if start == end {
+            return Ok(());
+        }
+
+        state
+            .map
+            .flush_range(start as usize, (end - start) as usize)?;
+        state.dirty.remove(start..end);
+        Ok(())
+    }
+
+    #[inline]
+    fn read(&self) -> RwLockReadGuard<'_, MutState> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    fn write(&self) -> RwLockWriteGuard<'_, MutState> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ReadAt for MmapFileMut {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.read().map[..].read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.read().map[..].read_exact_at(buf, offset)
+    }
+}
+
+impl WriteAt for MmapFileMut {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let mut state = self.write();
+        let map_len = state.map.len();
+        let start = min(offset, map_len as u64) as usize;
+        let len = min(buf.len(), map_len - start);
+
+        state.map[start..start + len].copy_from_slice(&buf[..len]);
+        state.dirty.insert(start as u64..(start + len) as u64);
+        Ok(len)
+    }
+
+    /// Synchronously writes back all dirty ranges to the file.
+    fn flush(&self) -> io::Result<()> {
+        let mut state = self.write();
+        let MutState { map, dirty } = &mut *state;
+        for range in dirty.iter() {
+            map.flush_range(range.start as usize, (range.end - range.start) as usize)?;
+        }
+        dirty.clear();
+        Ok(())
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.flush()?;
+        Ok(self.read().map.len() as u64)
+    }
+}
+
+impl Size for MmapFileMut {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.read().map.len() as u64)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.read().map.len() as u64)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefetch() {
+        let file = RandomAccessFile::open("LICENSE-APACHE").unwrap();
+        let map = unsafe { MmapFile::map(&file).unwrap() };
+        let contents = std::fs::read("LICENSE-APACHE").unwrap();
+        assert_eq!(map.len(), contents.len());
+
+        map.prefetch(100, 200).unwrap();
+        let mut buf = [0; 200];
+        map.read_exact_at(&mut buf, 100).unwrap();
+        assert_eq!(&buf[..], &contents[100..300]);
+
+        map.evict(0, u64::MAX).unwrap();
+        map.read_exact_at(&mut buf, 100).unwrap();
+        assert_eq!(&buf[..], &contents[100..300]);
+
+        map.prefetch(1 << 40, 10).unwrap();
+    }
+
+    #[test]
+    fn write_and_flush() {
+        let path = crate::tests::TempPath::new("mmap_write");
+        let file = RandomAccessFile::from(path.create_rw());
+        file.set_len(3 * 4096).unwrap();
+
+        let map = unsafe { MmapFileMut::map_mut(&file).unwrap() };
+        map.write_all_at(b"Hello", 10).unwrap();
+        map.write_all_at(b" World!", 15).unwrap();
+        map.write_all_at(b"Rust", 5000).unwrap();
+        assert_eq!(map.write_at(b"Bye", 3 * 4096 - 1).unwrap(), 1);
+        assert_eq!(
+            map.dirty_ranges().iter().collect::<Vec<_>>(),
+            [10..22, 5000..5004, 12287..12288]
+        );
+
+        map.flush_range(0, 4096).unwrap();
+        assert_eq!(
+            map.dirty_ranges().iter().collect::<Vec<_>>(),
+            [5000..5004, 12287..12288]
+        );
+        map.flush().unwrap();
+        assert!(map.dirty_ranges().is_empty());
+        drop(map);
+
+        let map = unsafe { MmapFile::map(&file).unwrap() };
+        assert_eq!(&map.as_slice()[10..22], b"Hello World!");
+        assert_eq!(&map.as_slice()[5000..5004], b"Rust");
+        assert_eq!(map.as_slice()[12287], b'B');
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    fmt, io,
+    sync::{PoisonError, RwLock},
+};
+
+type Observer = Box<dyn Fn(u64, usize) + Send + Sync>;
+
+/// A writer that notifies observers of the regions it writes.
+///
+/// Observers are registered with [`on_write`](Self::on_write), and called
+/// with the offset and length of each successful write, after it is done, in
+/// the order in which they were registered. Empty writes are not notified.
+///
+/// Observers are called while holding a lock on the list of observers, so
+/// they must not register other observers.
+pub struct Observable<W> {
+    inner: W,
+    observers: RwLock<Vec<Observer>>,
+}
+
+impl<W> Observable<W> {
+    /// Creates a new `Observable` writer, without observers.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            observers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Registers an observer, called with the offset and length of each
+    /// write.
+    pub fn on_write<F>(&self, observer: F)
+    where
+        F: Fn(u64, usize) + Send + Sync + 'static,
+    {
+        self.observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(observer));
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn notify(&self, offset: u64, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        let observers = self
+            .observers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        for observer in &*observers {
+            observer(offset, len);
+        }
+    }
+}
+
+impl<W: WriteAt> WriteAt for Observable<W> {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let n = self.inner.write_at(buf, offset)?;
+        self.notify(offset, n);
+        Ok(n)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.inner.write_all_at(buf, offset)?;
+        self.notify(offset, buf.len());
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<W: ReadAt> ReadAt for Observable<W> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<W: Size> Size for Observable<W> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for Observable<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observable")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn notifies_writes() {
+        let writer = Observable::new(MemFile::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events2 = events.clone();
+        writer.on_write(move |offset, len| events2.lock().unwrap().push((offset, len)));
+
+        writer.write_all_at(b"Hello World!", 0).unwrap();
+        writer.write_all_at(b"", 3).unwrap();
+        assert_eq!(writer.write_at(b"Rust", 6).unwrap(), 4);
+
+        assert_eq!(*events.lock().unwrap(), [(0, 12), (6, 4)]);
+        assert_eq!(writer.into_inner().contents(), b"Hello Rustd!");
+    }
+}
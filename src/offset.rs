@@ -0,0 +1,146 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::io;
+
+/// A source whose offsets start at a fixed position of the inner source.
+///
+/// Reading or writing at offset `o` reads or writes the inner source at offset
+/// `base + o`, and the size is the size of the inner source after `base`.
+///
+/// This struct is usually created by [`ReadAt::skip_preamble`], or by
+/// `Framed::body` to access the body of a framed source.
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetReader<R> {
+    inner: R,
+    base: u64,
+}
+
+impl<R> OffsetReader<R> {
+    /// Creates a new `OffsetReader` reading `inner` from `base`.
+    #[inline]
+    pub fn new(inner: R, base: u64) -> Self {
+        Self { inner, base }
+    }
+
+    /// Returns the offset in the inner reader at which this reader starts.
+    #[must_use]
+    #[inline]
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    #[inline]
+    fn offset(&self, offset: u64) -> io::Result<u64> {
+        add_base(self.base, offset)
+    }
+}
+
+/// Translates an offset relative to `base` to an offset of the inner source.
+#[inline]
+pub(crate) fn add_base(base: u64, offset: u64) -> io::Result<u64> {
+    offset.checked_add(base).ok_or_else(offset_overflow)
+}
+
+#[cold]
+fn offset_overflow() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "offset overflow")
+}
+
+impl<R: ReadAt> ReadAt for OffsetReader<R> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, self.offset(offset)?)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, self.offset(offset)?)
+    }
+}
+
+impl<R: WriteAt> WriteAt for OffsetReader<R> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.inner.write_at(buf, self.offset(offset)?)
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.inner.write_all_at(buf, self.offset(offset)?)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        Ok(self.inner.flush_tracked()?.saturating_sub(self.base))
+    }
+}
+
+impl<R: Size> Size for OffsetReader<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.inner.size()?.saturating_sub(self.base))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.inner.size_hint()?.saturating_sub(self.base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetReader;
+    use crate::{tests::MemFile, ReadAt, Size, WriteAt};
+    use std::io;
+
+    #[test]
+    fn skip_preamble() {
+        let reader = b"MAGIC\x01Hello World!"
+            .skip_preamble(b"MAGIC\x01")
+            .unwrap();
+        assert_eq!(reader.base(), 6);
+        assert_eq!(reader.size().unwrap(), 12);
+
+        let mut buf = [0; 5];
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+    }
+
+    #[test]
+    fn writes() {
+        let file = MemFile::default();
+        let writer = OffsetReader::new(&file, 4);
+        writer.write_all_at(b"World", 2).unwrap();
+        assert_eq!(file.contents(), b"\0\0\0\0\0\0World");
+        assert_eq!(writer.size().unwrap(), 7);
+        assert_eq!(writer.flush_tracked().unwrap(), 7);
+
+        let err = writer.write_at(b"!", u64::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn wrong_magic() {
+        let err = b"MAGIC\x02Hello".skip_preamble(b"MAGIC\x01").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = b"MAG".skip_preamble(b"MAGIC\x01").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(feature = "flate2")]
+use crate::Decompressed;
+#[cfg(feature = "chacha20poly1305")]
+use crate::SealedReader;
+use crate::{Adapter, OffsetReader, ReadAt, Size};
+#[cfg(any(feature = "flate2", feature = "chacha20poly1305"))]
+use std::io;
+
+/// A builder stacking transforms over a source.
+///
+/// Each step wraps the current source in an adapter, starting from the raw
+/// source, so steps are listed in the order in which they undo the encoding:
+///
+/// ```no_run
+/// # #[cfg(all(feature = "flate2", feature = "chacha20poly1305"))]
+/// # fn main() -> std::io::Result<()> {
+/// use std::io::Read;
+/// use sync_file::{Pipeline, RandomAccessFile};
+///
+/// let key = [0; 32];
+/// let file = RandomAccessFile::open("data.bin")?;
+/// let mut reader = Pipeline::new(file)
+///     .decrypt(&key, *b"data", 4096)?
+///     .decompress()?
+///     .reader();
+///
+/// let mut data = Vec::new();
+/// reader.read_to_end(&mut data)?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "flate2", feature = "chacha20poly1305")))]
+/// # fn main() {}
+/// ```
+///
+/// # Seeking
+///
+/// All transforms are positional: each read of [`SealedReader`] and
+/// [`Decompressed`] only decodes the chunks or blocks covering it, and they
+/// keep no state that depends on previous reads, apart from caches. Seeking
+/// the reader returned by [`reader`](Self::reader) is thus supported whatever
+/// the transforms, and only moves its cursor.
+///
+/// [`SealedReader`]: crate::SealedReader
+/// [`Decompressed`]: crate::Decompressed
+#[derive(Debug)]
+pub struct Pipeline<R> {
+    inner: R,
+}
+
+impl<R> Pipeline<R> {
+    /// Starts a pipeline from a raw source.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    /// Skips the first `base` bytes of the source.
+    ///
+    /// See [`OffsetReader`] for details.
+    #[inline]
+    pub fn offset(self, base: u64) -> Pipeline<OffsetReader<R>> {
+        Pipeline::new(OffsetReader::new(self.inner, base))
+    }
+
+    /// Returns a reader implementing [`std::io::Read`] and [`std::io::Seek`]
+    /// over the transformed source, starting at its beginning.
+    #[inline]
+    pub fn reader(self) -> Adapter<R> {
+        Adapter::new(self.inner)
+    }
+
+    /// Returns the transformed source, which implements [`ReadAt`] and
+    /// [`Size`].
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ReadAt + Size> Pipeline<R> {
+    /// Decrypts data sealed by a [`SealedWriter`](crate::SealedWriter).
+    ///
+    /// See [`SealedReader::new`] for details.
+    #[cfg(feature = "chacha20poly1305")]
+    #[inline]
+    pub fn decrypt(
+        self,
+        key: &[u8; 32],
+        nonce_prefix: [u8; 4],
+        chunk_size: usize,
+    ) -> io::Result<Pipeline<SealedReader<R>>> {
+        SealedReader::new(self.inner, key, nonce_prefix, chunk_size).map(Pipeline::new)
+    }
+
+    /// Decompresses data written by a
+    /// [`CompressedWriter`](crate::CompressedWriter).
+    ///
+    /// See [`Decompressed::new`] for details.
+    #[cfg(feature = "flate2")]
+    #[inline]
+    pub fn decompress(self) -> io::Result<Pipeline<Decompressed<R>>> {
+        Decompressed::new(self.inner).map(Pipeline::new)
+    }
+}
+
+#[cfg(all(test, feature = "flate2", feature = "chacha20poly1305"))]
+mod tests {
+    use super::*;
+    use crate::{tests::MemFile, CompressedWriter, SealedWriter, WriteAt};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn decrypt_and_decompress() {
+        let data: Vec<u8> = (0..100u8).flat_map(|i| [i; 10]).collect();
+
+        let compressed = CompressedWriter::new(MemFile::default(), 64);
+        compressed.write_all_at(&data, 0).unwrap();
+        compressed.flush().unwrap();
+
+        let key = [7; 32];
+        let mut sealed = SealedWriter::new(b"HEADER".to_vec(), &key, *b"test", 32);
+        sealed
+            .write_all(&compressed.into_inner().contents())
+            .unwrap();
+        let sealed = sealed.finish().unwrap();
+
+        let mut reader = Pipeline::new(sealed)
+            .offset(6)
+            .decrypt(&key, *b"test", 32)
+            .unwrap()
+            .decompress()
+            .unwrap()
+            .reader();
+
+        let mut plaintext = Vec::new();
+        reader.read_to_end(&mut plaintext).unwrap();
+        assert_eq!(plaintext, data);
+
+        let mut buf = [0; 20];
+        reader.seek(SeekFrom::Start(495)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[..], data[495..515]);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::ReadAt;
+use std::{fmt, io, marker::PhantomData, mem::size_of};
+
+pub(crate) fn read_struct_at<R, T>(reader: &R, index: u64) -> io::Result<T>
+where
+    R: ReadAt + ?Sized,
+    T: bytemuck::Pod,
+{
+    let offset = index
+        .checked_mul(size_of::<T>() as u64)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))?;
+
+    let mut value = T::zeroed();
+    reader.read_exact_at(bytemuck::bytes_of_mut(&mut value), offset)?;
+    Ok(value)
+}
+
+/// An iterator over the values of an array of `T`.
+///
+/// This type is created by [`ReadAt::struct_iter`].
+pub struct StructIter<'a, R: ?Sized, T> {
+    reader: &'a R,
+    index: u64,
+    count: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, R: ?Sized, T> StructIter<'a, R, T> {
+    #[inline]
+    pub(crate) fn new(reader: &'a R, count: u64) -> Self {
+        Self {
+            reader,
+            index: 0,
+            count,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R, T> Iterator for StructIter<'_, R, T>
+where
+    R: ReadAt + ?Sized,
+    T: bytemuck::Pod,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<io::Result<T>> {
+        if self.index == self.count {
+            return None;
+        }
+
+        let value = read_struct_at(self.reader, self.index);
+        self.index += 1;
+        Some(value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.count - self.index).try_into().unwrap_or(usize::MAX);
+        (len, Some(len))
+    }
+}
+
+impl<R: ?Sized, T> fmt::Debug for StructIter<'_, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructIter")
+            .field("index", &self.index)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::TempPath, RandomAccessFile, ReadAt, WriteAt};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    unsafe impl bytemuck::Zeroable for Point {}
+    unsafe impl bytemuck::Pod for Point {}
+
+    #[test]
+    fn read_structs() {
+        let points: Vec<_> = (0..10).map(|i| Point { x: i, y: 2 * i }).collect();
+
+        let path = TempPath::new("read_structs");
+        let file = RandomAccessFile::from(path.create_rw());
+        file.write_all_at(bytemuck::cast_slice(&points), 0).unwrap();
+
+        assert_eq!(file.read_struct_at::<Point>(3).unwrap(), points[3]);
+        assert!(file.read_struct_at::<Point>(10).is_err());
+
+        let read: Vec<Point> = file.struct_iter(10).collect::<Result<_, _>>().unwrap();
+        assert_eq!(read, points);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    alloc::{self, Layout},
+    fmt,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+/// A heap buffer with a given alignment.
+struct AlignedBuf {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// SAFETY: `AlignedBuf` owns its memory, like a `Box<[u8]>`.
+unsafe impl Send for AlignedBuf {}
+unsafe impl Sync for AlignedBuf {}
+
+impl AlignedBuf {
+    fn new(layout: Layout) -> Self {
+        // SAFETY: the layout has a non-zero size.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        match NonNull::new(ptr) {
+            Some(ptr) => Self { ptr, layout },
+            None => alloc::handle_alloc_error(layout),
+        }
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: the buffer is allocated and initialized.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: the buffer is allocated and initialized.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        // SAFETY: the buffer was allocated with this layout.
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+/// A pool of reusable aligned buffers.
+///
+/// All buffers have the same size and alignment. They are allocated on
+/// demand, and returned to the pool when the [`PooledBuf`] that holds them is
+/// dropped, so a loop that reads into pooled buffers stops allocating once
+/// the pool is warm. The pool never frees buffers until it is dropped.
+///
+/// See [`ReadAt::read_at_pooled`](crate::ReadAt::read_at_pooled).
+pub struct BufferPool {
+    layout: Layout,
+    free: Mutex<Vec<AlignedBuf>>,
+    allocations: AtomicUsize,
+}
+
+impl BufferPool {
+    /// Creates a new pool of buffers of `size` bytes aligned to `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, if `align` is not a power of two, or if
+    /// `size` rounded up to `align` overflows an `isize`.
+    pub fn new(size: usize, align: usize) -> Self {
+        assert!(size != 0, "buffer size must be non-zero");
+        let layout = Layout::from_size_align(size, align).expect("invalid buffer layout");
+
+        Self {
+            layout,
+            free: Mutex::new(Vec::new()),
+            allocations: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the size of buffers.
+    #[must_use]
+    #[inline]
+    pub fn buffer_size(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// Returns the alignment of buffers.
+    #[must_use]
+    #[inline]
+    pub fn alignment(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// Returns the number of buffers allocated by this pool so far.
+    #[must_use]
+    #[inline]
+    pub fn allocations(&self) -> usize {
+        self.allocations.load(Ordering::Relaxed)
+    }
+
+    /// Takes a buffer from the pool, allocating one if none is free.
+    ///
+    /// The contents of the buffer are unspecified, as it may have been used
+    /// before.
+    pub fn get(&self) -> PooledBuf<'_> {
+        let buf = self
+            .free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        let buf = buf.unwrap_or_else(|| {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            AlignedBuf::new(self.layout)
+        });
+
+        PooledBuf {
+            buf: Some(buf),
+            len: self.layout.size(),
+            pool: self,
+        }
+    }
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("buffer_size", &self.buffer_size())
+            .field("alignment", &self.alignment())
+            .field("allocations", &self.allocations())
+            .finish()
+    }
+}
+
+/// A buffer borrowed from a [`BufferPool`], which is returned to it on drop.
+///
+/// It dereferences to the first [`len`](Self::len) bytes of the buffer.
+pub struct PooledBuf<'a> {
+    buf: Option<AlignedBuf>,
+    len: usize,
+    pool: &'a BufferPool,
+}
+
+impl PooledBuf<'_> {
+    /// Returns the number of bytes in use.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no byte is in use.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Shortens the part of the buffer in use.
+    ///
+    /// This has no effect if `len` is greater than the current length.
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+}
+
+impl Deref for PooledBuf<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.buf.as_ref().unwrap().as_slice()[..self.len]
+    }
+}
+
+impl DerefMut for PooledBuf<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.buf.as_mut().unwrap().as_mut_slice()[..self.len]
+    }
+}
+
+impl Drop for PooledBuf<'_> {
+    fn drop(&mut self) {
+        if let Some(buf) = self.buf.take() {
+            self.pool
+                .free
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(buf);
+        }
+    }
+}
+
+impl fmt::Debug for PooledBuf<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledBuf").field("len", &self.len).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RandomAccessFile, ReadAt};
+
+    #[test]
+    fn reuses_buffers() {
+        let pool = BufferPool::new(4096, 512);
+        let file = RandomAccessFile::open("LICENSE-APACHE").unwrap();
+        let contents = std::fs::read("LICENSE-APACHE").unwrap();
+
+        for offset in (0..contents.len() as u64).step_by(1000) {
+            let buf = file.read_at_pooled(100, offset, &pool).unwrap();
+            assert_eq!(buf.as_ptr() as usize % 512, 0);
+            assert_eq!(&buf[..], &contents[offset as usize..][..buf.len()]);
+        }
+        assert_eq!(pool.allocations(), 1);
+
+        let (a, b) = (pool.get(), pool.get());
+        assert_eq!(pool.allocations(), 2);
+        drop((a, b));
+        let _c = pool.get();
+        assert_eq!(pool.allocations(), 2);
+
+        assert!(file.read_at_pooled(5000, 0, &pool).is_err());
+    }
+}
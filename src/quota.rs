@@ -0,0 +1,150 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::io;
+
+/// A writer that prevents a source from growing past a maximum length.
+///
+/// Writes that end after `max_len` fail with an error of kind
+/// [`io::ErrorKind::Other`], unless they are within the current size of the
+/// inner source, so existing data can always be overwritten. A call to
+/// [`write_at`](WriteAt::write_at) that starts before the limit writes the
+/// bytes up to it and returns a short count.
+#[derive(Debug)]
+pub struct Quota<W> {
+    inner: W,
+    max_len: u64,
+}
+
+impl<W> Quota<W> {
+    /// Creates a new `Quota` writer allowing the source to grow up to
+    /// `max_len` bytes.
+    #[inline]
+    pub fn new(inner: W, max_len: u64) -> Self {
+        Self { inner, max_len }
+    }
+
+    /// Returns the maximum length of the source.
+    #[must_use]
+    #[inline]
+    pub fn max_len(&self) -> u64 {
+        self.max_len
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Size> Quota<W> {
+    /// Returns how many bytes of a write of `len` bytes at `offset` are
+    /// allowed.
+    fn allowed_len(&self, offset: u64, len: usize) -> io::Result<usize> {
+        let end = offset.saturating_add(len as u64);
+        if end <= self.max_len || end <= self.inner.size()? {
+            return Ok(len);
+        }
+        Ok(self.max_len.saturating_sub(offset) as usize)
+    }
+}
+
+impl<W: WriteAt + Size> WriteAt for Quota<W> {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        match self.allowed_len(offset, buf.len())? {
+            0 if !buf.is_empty() => Err(quota_exceeded()),
+            len => self.inner.write_at(&buf[..len], offset),
+        }
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        if self.allowed_len(offset, buf.len())? < buf.len() {
+            return Err(quota_exceeded());
+        }
+        self.inner.write_all_at(buf, offset)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<W: ReadAt> ReadAt for Quota<W> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<W: Size> Size for Quota<W> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cold]
+fn quota_exceeded() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "write exceeds the size quota")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn within_quota() {
+        let writer = Quota::new(MemFile::default(), 12);
+        writer.write_all_at(b"Hello World!", 0).unwrap();
+        writer.write_all_at(b"Rust!", 6).unwrap();
+        assert_eq!(writer.get_ref().contents(), b"Hello Rust!!");
+    }
+
+    #[test]
+    fn exceeds_quota() {
+        let writer = Quota::new(MemFile::default(), 8);
+        let err = writer.write_all_at(b"Hello World!", 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(writer.get_ref().contents(), b"");
+
+        let err = writer.write_at(b"!", 8).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+
+        // Writes within the existing bounds succeed even past the limit.
+        let writer = Quota::new(writer.into_inner(), 12);
+        writer.write_all_at(b"Hello World!", 0).unwrap();
+        let writer = Quota::new(writer.into_inner(), 8);
+        writer.write_all_at(b"Rust", 6).unwrap();
+        assert_eq!(writer.get_ref().contents(), b"Hello Rustd!");
+    }
+
+    #[test]
+    fn partial_write() {
+        let writer = Quota::new(MemFile::default(), 8);
+        assert_eq!(writer.write_at(b"Hello World!", 0).unwrap(), 8);
+        assert_eq!(writer.get_ref().contents(), b"Hello Wo");
+        assert_eq!(writer.write_at(b"", 8).unwrap(), 0);
+    }
+}
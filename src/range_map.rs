@@ -0,0 +1,145 @@
+//! A sparse map of byte ranges, used to keep writes in memory.
+
+use std::collections::BTreeMap;
+
+/// Non-overlapping byte ranges, indexed by their offset.
+///
+/// Inserting a range overwrites the bytes it overlaps, and merges it with
+/// the ranges it overlaps or touches.
+#[derive(Debug, Default)]
+pub(crate) struct RangeMap {
+    ranges: BTreeMap<u64, Vec<u8>>,
+    bytes: usize,
+}
+
+impl RangeMap {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of bytes stored.
+    #[inline]
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the end of the last range.
+    pub(crate) fn end(&self) -> Option<u64> {
+        let (&start, data) = self.ranges.iter().next_back()?;
+        Some(start + data.len() as u64)
+    }
+
+    /// Returns the number of bytes that would be stored after inserting
+    /// `len` bytes at `offset`.
+    pub(crate) fn bytes_after_insert(&self, offset: u64, len: usize) -> usize {
+        let end = offset + len as u64;
+        let overlapped: u64 = self
+            .ranges
+            .range(..end)
+            .rev()
+            .take_while(|(&start, data)| start + data.len() as u64 > offset)
+            .map(|(&start, data)| {
+                let range_end = start + data.len() as u64;
+                range_end.min(end) - start.max(offset)
+            })
+            .sum();
+        self.bytes + len - overlapped as usize
+    }
+
+    /// Inserts bytes at `offset`, overwriting existing ones.
+    ///
+    /// The end of the range must not overflow a `u64`.
+    pub(crate) fn insert(&mut self, offset: u64, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let end = offset + data.len() as u64;
+
+        // Ranges are disjoint and not adjacent, so the ones to merge are
+        // contiguous in the map.
+        let merged: Vec<u64> = self
+            .ranges
+            .range(..=end)
+            .rev()
+            .take_while(|(&start, data)| start + data.len() as u64 >= offset)
+            .map(|(&start, _)| start)
+            .collect();
+
+        let mut new_start = offset;
+        let mut new_end = end;
+        for start in &merged {
+            let len = self.ranges[start].len() as u64;
+            new_start = new_start.min(*start);
+            new_end = new_end.max(start + len);
+        }
+
+        let mut buf = vec![0; (new_end - new_start) as usize];
+        for start in merged {
+            let old = self.ranges.remove(&start).unwrap();
+            self.bytes -= old.len();
+            let pos = (start - new_start) as usize;
+            buf[pos..pos + old.len()].copy_from_slice(&old);
+        }
+        let pos = (offset - new_start) as usize;
+        buf[pos..pos + data.len()].copy_from_slice(data);
+
+        self.bytes += buf.len();
+        self.ranges.insert(new_start, buf);
+    }
+
+    /// Copies the stored bytes overlapping `buf` into it, as if it was read
+    /// from `offset`.
+    pub(crate) fn overlay(&self, buf: &mut [u8], offset: u64) {
+        let end = offset.saturating_add(buf.len() as u64);
+        let overlapping = self
+            .ranges
+            .range(..end)
+            .rev()
+            .take_while(|(&start, data)| start + data.len() as u64 > offset);
+
+        for (&start, data) in overlapping {
+            let from = start.max(offset);
+            let to = (start + data.len() as u64).min(end);
+            let src = &data[(from - start) as usize..(to - start) as usize];
+            buf[(from - offset) as usize..(to - offset) as usize].copy_from_slice(src);
+        }
+    }
+
+    /// Iterates over the ranges, in offset order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u64, &[u8])> + '_ {
+        self.ranges.iter().map(|(&start, data)| (start, &data[..]))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.ranges.clear();
+        self.bytes = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_ranges() {
+        let mut map = RangeMap::new();
+        map.insert(10, b"World");
+        map.insert(0, b"Hello");
+        assert_eq!(map.bytes_after_insert(3, 10), 15);
+        map.insert(5, b"_");
+        map.insert(8, b"__");
+        assert_eq!(map.bytes(), 13);
+        assert_eq!(map.end(), Some(15));
+
+        let ranges: Vec<_> = map.iter().collect();
+        assert_eq!(ranges, [(0, &b"Hello_"[..]), (8, b"__World")]);
+
+        let mut buf = *b"..........";
+        map.overlay(&mut buf, 4);
+        assert_eq!(&buf, b"o_..__Worl");
+
+        map.clear();
+        assert_eq!(map.end(), None);
+    }
+}
@@ -0,0 +1,282 @@
+use std::{collections::BTreeMap, io, ops::Range};
+
+/// A set of `u64` offsets, stored as disjoint ranges.
+///
+/// Inserted ranges are merged with the ranges they overlap or touch, so the
+/// set is always made of the fewest possible ranges. This is typically used
+/// to track which regions of a file hold valid data, and can be
+/// [serialized](Self::to_bytes) to be persisted alongside it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    /// Ranges indexed by their start, mapped to their end.
+    ranges: BTreeMap<u64, u64>,
+}
+
+impl RangeSet {
+    /// Creates an empty `RangeSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the set contains no offset.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns the number of disjoint ranges in the set.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns the number of offsets in the set.
+    #[must_use]
+    pub fn covered(&self) -> u64 {
+        self.ranges.iter().map(|(start, end)| end - start).sum()
+    }
+
+    /// Adds a range to the set.
+    pub fn insert(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let merged: Vec<(u64, u64)> = self
+            .ranges
+            .range(..=range.end)
+            .rev()
+            .take_while(|(_, &end)| end >= range.start)
+            .map(|(&start, &end)| (start, end))
+            .collect();
+
+        let mut start = range.start;
+        let mut end = range.end;
+        for (s, e) in merged {
+            self.ranges.remove(&s);
+            start = start.min(s);
+            end = end.max(e);
+        }
+        self.ranges.insert(start, end);
+    }
+
+    /// Removes a range from the set.
+    ///
+    /// Ranges of the set that partially overlap `range` are shrunk or split.
+    pub fn remove(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let overlapping: Vec<(u64, u64)> = self
+            .ranges
+            .range(..range.end)
+            .rev()
+            .take_while(|(_, &end)| end > range.start)
+            .map(|(&start, &end)| (start, end))
+            .collect();
+
+        for (start, end) in overlapping {
+            self.ranges.remove(&start);
+            if start < range.start {
+                self.ranges.insert(start, range.start);
+            }
+            if range.end < end {
+                self.ranges.insert(range.end, end);
+            }
+        }
+    }
+
+    /// Returns `true` if the set contains `offset`.
+    #[must_use]
+    pub fn contains(&self, offset: u64) -> bool {
+        self.ranges
+            .range(..=offset)
+            .next_back()
+            .map_or(false, |(_, &end)| offset < end)
+    }
+
+    /// Returns `true` if the set contains all offsets of `range`.
+    ///
+    /// An empty range is always contained.
+    #[must_use]
+    pub fn contains_range(&self, range: Range<u64>) -> bool {
+        range.is_empty()
+            || self
+                .ranges
+                .range(..=range.start)
+                .next_back()
+                .map_or(false, |(_, &end)| range.end <= end)
+    }
+
+    /// Returns an iterator over the ranges of `within` that are not in the
+    /// set, in order.
+    pub fn gaps(&self, within: Range<u64>) -> impl Iterator<Item = Range<u64>> + '_ {
+        let end = within.end.max(within.start);
+        let from = match self.ranges.range(..=within.start).next_back() {
+            Some((&start, _)) => start,
+            None => within.start,
+        };
+        let mut covered = self.ranges.range(from..end);
+        let mut pos = within.start;
+
+        std::iter::from_fn(move || {
+            while pos < end {
+                match covered.next() {
+                    Some((&start, &range_end)) if start > pos => {
+                        let gap = pos..start;
+                        pos = range_end;
+                        return Some(gap);
+                    }
+                    Some((_, &range_end)) => pos = pos.max(range_end),
+                    None => {
+                        let gap = pos..end;
+                        pos = end;
+                        return Some(gap);
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns an iterator over the ranges of the set, in order.
+    pub fn iter(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.ranges.iter().map(|(&start, &end)| start..end)
+    }
+
+    /// Removes all ranges from the set.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    /// Serializes the set.
+    ///
+    /// The format is the number of ranges, followed by the start and end of
+    /// each range in order, all as little-endian `u64`.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 16 * self.ranges.len());
+        bytes.extend_from_slice(&(self.ranges.len() as u64).to_le_bytes());
+        for (start, end) in &self.ranges {
+            bytes.extend_from_slice(&start.to_le_bytes());
+            bytes.extend_from_slice(&end.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserializes a set serialized with [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if `bytes` is
+    /// not a valid serialized set.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut words = bytes
+            .chunks(8)
+            .map(|chunk| Some(u64::from_le_bytes(chunk.try_into().ok()?)));
+        let mut next = || words.next().flatten().ok_or_else(invalid_data);
+
+        let count = next()?;
+        if bytes.len() as u64 != count.saturating_mul(16).saturating_add(8) {
+            return Err(invalid_data());
+        }
+
+        let mut ranges = BTreeMap::new();
+        let mut prev_end = None;
+        for _ in 0..count {
+            let (start, end) = (next()?, next()?);
+            if start >= end || prev_end.map_or(false, |prev| start <= prev) {
+                return Err(invalid_data());
+            }
+            ranges.insert(start, end);
+            prev_end = Some(end);
+        }
+
+        Ok(Self { ranges })
+    }
+}
+
+#[cold]
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid serialized range set")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[Range<u64>]) -> RangeSet {
+        let mut set = RangeSet::new();
+        for range in ranges {
+            set.insert(range.clone());
+        }
+        set
+    }
+
+    #[test]
+    fn merges_ranges() {
+        let mut set = set(&[10..20, 30..40, 0..5]);
+        assert_eq!(set.len(), 3);
+
+        set.insert(5..10);
+        set.insert(15..35);
+        assert_eq!(set.iter().next(), Some(0..40));
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.covered(), 40);
+
+        set.insert(50..60);
+        assert!(set.contains(0));
+        assert!(!set.contains(40));
+        assert!(set.contains(59));
+        assert!(set.contains_range(10..40));
+        assert!(!set.contains_range(30..55));
+    }
+
+    #[test]
+    fn remove() {
+        let mut set = set(&[0..10, 20..30, 40..50]);
+        set.remove(5..25);
+        assert_eq!(set, self::set(&[0..5, 25..30, 40..50]));
+
+        set.remove(42..45);
+        set.remove(30..40);
+        assert_eq!(set, self::set(&[0..5, 25..30, 40..42, 45..50]));
+
+        set.remove(0..u64::MAX);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn gaps() {
+        let set = set(&[10..20, 30..40]);
+        let gaps = |range| set.gaps(range).collect::<Vec<_>>();
+
+        assert_eq!(gaps(0..50), [0..10, 20..30, 40..50]);
+        assert_eq!(gaps(15..35), vec![20..30]);
+        assert_eq!(gaps(12..18), []);
+        assert_eq!(gaps(25..28), vec![25..28]);
+        assert_eq!(gaps(20..30), vec![20..30]);
+        assert_eq!(gaps(5..5), []);
+    }
+
+    #[test]
+    fn serialization() {
+        let set = set(&[10..20, 30..40, 100..u64::MAX]);
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), 8 + 3 * 16);
+        assert_eq!(RangeSet::from_bytes(&bytes).unwrap(), set);
+        assert_eq!(
+            RangeSet::from_bytes(&RangeSet::new().to_bytes()).unwrap(),
+            RangeSet::new()
+        );
+
+        assert!(RangeSet::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let overlapping = [&2u64.to_le_bytes()[..], &[0; 8], &[5; 8], &[1; 8], &[9; 8]].concat();
+        assert!(RangeSet::from_bytes(&overlapping).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    io,
+    sync::{Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A wrapper that limits the throughput of reads and writes.
+///
+/// Throughput is limited with a token bucket, which holds up to one second of
+/// transfer and starts empty. Each call takes as many tokens as the length of
+/// its buffer before reaching the inner source, and sleeps if there are not
+/// enough of them. Reads and writes share the same bucket.
+///
+/// Fairness is coarse-grained: a large call takes all its tokens at once, and
+/// calls that come after it sleep until it is paid for.
+#[derive(Debug)]
+pub struct RateLimited<T> {
+    inner: T,
+    rate: u64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Available tokens. This is negative if callers are waiting.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl<T> RateLimited<T> {
+    /// Creates a new `RateLimited` wrapper allowing `bytes_per_second`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_second` is zero.
+    pub fn new(inner: T, bytes_per_second: u64) -> Self {
+        assert!(bytes_per_second != 0, "rate must be non-zero");
+
+        Self {
+            inner,
+            rate: bytes_per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the configured rate, in bytes per second.
+    #[must_use]
+    #[inline]
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Takes `len` tokens from the bucket, sleeping if there are not enough.
+    fn acquire(&self, len: usize) {
+        let rate = self.rate as f64;
+
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+            bucket.last_refill = now;
+
+            bucket.tokens -= len as f64;
+            -bucket.tokens / rate
+        };
+
+        if wait > 0.0 {
+            thread::sleep(Duration::from_secs_f64(wait));
+        }
+    }
+}
+
+impl<T: ReadAt> ReadAt for RateLimited<T> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.acquire(buf.len());
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.acquire(buf.len());
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<T: WriteAt> WriteAt for RateLimited<T> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.acquire(buf.len());
+        self.inner.write_at(buf, offset)
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.acquire(buf.len());
+        self.inner.write_all_at(buf, offset)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<T: Size> Size for RateLimited<T> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn limits_writes() {
+        let writer = RateLimited::new(MemFile::default(), 10_000);
+
+        let start = Instant::now();
+        for i in 0..4 {
+            writer.write_all_at(&[i; 500], i as u64 * 500).unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(190));
+        assert_eq!(writer.get_ref().contents().len(), 2000);
+    }
+}
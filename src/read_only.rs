@@ -0,0 +1,107 @@
+use crate::{ReadAt, Size};
+use std::io;
+
+/// A wrapper that only allows reading from its inner source.
+///
+/// `ReadOnly` implements [`ReadAt`] and [`Size`], as well as [`io::Read`] and
+/// [`io::Seek`] for cursor-based sources, but never [`WriteAt`] or
+/// [`io::Write`], even if the inner type does. The inner value cannot be retrieved either, so this
+/// can be used to hand out a file without giving the ability to write to it.
+///
+/// ```compile_fail
+/// use sync_file::{RandomAccessFile, ReadOnly, WriteAt};
+///
+/// let file = RandomAccessFile::open("Cargo.toml")?;
+/// let file = ReadOnly::new(file);
+/// file.write_at(b"Hello", 0)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+///
+/// [`WriteAt`]: crate::WriteAt
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOnly<R>(R);
+
+impl<R: ReadAt> ReadOnly<R> {
+    /// Creates a new `ReadOnly` wrapper.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self(inner)
+    }
+
+    /// Gets a reference to the underlying reader.
+    ///
+    /// Only its [`ReadAt`] implementation is exposed.
+    #[inline]
+    pub fn get_ref(&self) -> &dyn ReadAt {
+        &self.0
+    }
+}
+
+impl<R: ReadAt> ReadAt for ReadOnly<R> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.0.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.0.read_exact_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        self.0.read_vectored_at(bufs, offset)
+    }
+}
+
+impl<R: ReadAt + io::Read> io::Read for ReadOnly<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    #[inline]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
+}
+
+impl<R: ReadAt + io::Seek> io::Seek for ReadOnly<R> {
+    #[inline]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl<R: Size> Size for ReadOnly<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.0.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RandomAccessFile;
+
+    #[test]
+    fn reads() {
+        let file = ReadOnly::new(RandomAccessFile::open("LICENSE-MIT").unwrap());
+        let mut buf = [0; 15];
+
+        file.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Copyright 2021 ");
+        file.get_ref().read_exact_at(&mut buf[..4], 0).unwrap();
+        assert_eq!(&buf[..4], b"Copy");
+        assert_eq!(
+            file.size().unwrap(),
+            std::fs::metadata("LICENSE-MIT").unwrap().len()
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Read-ahead for sequential reads.
+
+use crate::{ReadAt, SyncFile};
+use std::{cmp::min, io};
+
+/// A file that reads ahead when used as a stream.
+///
+/// After a read through [`io::Read`] misses its buffer, this reads `window`
+/// bytes at once from the file and serves the next sequential reads from
+/// them, which saves system calls when scanning a file in small reads. Reads
+/// at least as large as the window bypass the buffer.
+///
+/// The buffer starts empty, and clones have their own buffer. Seeking or
+/// writing through [`io::Write`] discards it, but positional writes and
+/// writes through other handles are not seen until then.
+///
+/// A window of zero disables read-ahead.
+#[derive(Debug, Clone)]
+pub struct ReadaheadFile {
+    file: SyncFile,
+    buffer: Readahead,
+}
+
+impl ReadaheadFile {
+    /// Creates a new `ReadaheadFile` reading ahead `window` bytes, starting
+    /// at the cursor of `file`.
+    #[inline]
+    pub fn new(file: SyncFile, window: usize) -> Self {
+        Self {
+            file,
+            buffer: Readahead::new(window),
+        }
+    }
+
+    /// Returns the number of bytes read ahead.
+    #[must_use]
+    #[inline]
+    pub fn window(&self) -> usize {
+        self.buffer.window
+    }
+
+    /// Returns the offset used when reading the file.
+    ///
+    /// See [`SyncFile::offset`] for details.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.file.offset()
+    }
+
+    /// Gets a reference to the underlying file.
+    #[inline]
+    pub fn get_ref(&self) -> &SyncFile {
+        &self.file
+    }
+
+    /// Unwraps the underlying file, discarding the buffered bytes.
+    ///
+    /// The cursor of the file is at the end of the bytes read through this
+    /// `ReadaheadFile`.
+    #[inline]
+    pub fn into_inner(self) -> SyncFile {
+        self.file
+    }
+}
+
+impl io::Read for ReadaheadFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.buffer.read(&self.file, buf, self.file.offset())?;
+        self.file.consume(n);
+        Ok(n)
+    }
+}
+
+impl io::Seek for ReadaheadFile {
+    #[inline]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.buffer.discard();
+        io::Seek::seek(&mut self.file, pos)
+    }
+
+    #[inline]
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.offset())
+    }
+}
+
+impl io::Write for ReadaheadFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.discard();
+        io::Write::write(&mut self.file, buf)
+    }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buffer.discard();
+        io::Write::write_all(&mut self.file, buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(&mut self.file)
+    }
+}
+
+/// A buffer holding the bytes that follow the last sequential read.
+///
+/// Reads are served from the buffer when they start within it. Otherwise,
+/// a window of bytes is read at once from the source and kept for the next
+/// reads. Reads that are at least as large as the window bypass the buffer.
+///
+/// A window of zero disables read-ahead.
+#[derive(Debug, Default)]
+pub(crate) struct Readahead {
+    window: usize,
+    buf: Vec<u8>,
+    offset: u64,
+}
+
+impl Readahead {
+    #[inline]
+    pub(crate) fn new(window: usize) -> Self {
+        Self {
+            window,
+            buf: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Discards buffered bytes.
+    #[inline]
+    pub(crate) fn discard(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Reads bytes from `offset`, going through the buffer.
+    pub(crate) fn read<R>(&mut self, reader: &R, buf: &mut [u8], offset: u64) -> io::Result<usize>
+    where
+        R: ReadAt + ?Sized,
+    {
+        if let Some(n) = self.copy_buffered(buf, offset) {
+            return Ok(n);
+        }
+
+        self.buf.clear();
+        if buf.len() >= self.window {
+            return reader.read_at(buf, offset);
+        }
+
+        self.buf.resize(self.window, 0);
+        match reader.read_at(&mut self.buf, offset) {
+            Ok(n) => {
+                self.buf.truncate(n);
+                self.offset = offset;
+            }
+            Err(e) => {
+                self.buf.clear();
+                return Err(e);
+            }
+        }
+        Ok(self.copy_buffered(buf, offset).unwrap_or(0))
+    }
+
+    /// Copies buffered bytes from `offset`, if there are any.
+    fn copy_buffered(&self, buf: &mut [u8], offset: u64) -> Option<usize> {
+        let start = offset.checked_sub(self.offset)?;
+        let buffered = self.buf.get(usize::try_from(start).ok()?..)?;
+        if buffered.is_empty() {
+            return None;
+        }
+
+        let len = min(buf.len(), buffered.len());
+        buf[..len].copy_from_slice(&buffered[..len]);
+        Some(len)
+    }
+}
+
+impl Clone for Readahead {
+    /// Clones the window, but not the buffered bytes.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new(self.window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct Counting<'a>(&'a [u8], Cell<usize>);
+
+    impl ReadAt for Counting<'_> {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            self.1.set(self.1.get() + 1);
+            self.0.read_at(buf, offset)
+        }
+    }
+
+    fn scan(reader: &Counting<'_>, window: usize) -> Vec<u8> {
+        let mut readahead = Readahead::new(window);
+        let mut contents = Vec::new();
+        let mut buf = [0; 10];
+        loop {
+            let n = readahead
+                .read(reader, &mut buf, contents.len() as u64)
+                .unwrap();
+            if n == 0 {
+                break contents;
+            }
+            contents.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    #[test]
+    fn sequential_scan() {
+        let data: Vec<u8> = (0..=255).collect();
+
+        let direct = Counting(&data, Cell::new(0));
+        assert_eq!(scan(&direct, 0), data);
+
+        let buffered = Counting(&data, Cell::new(0));
+        assert_eq!(scan(&buffered, 100), data);
+        assert_eq!(buffered.1.get(), 4);
+        assert!(buffered.1.get() < direct.1.get());
+    }
+}
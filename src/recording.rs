@@ -0,0 +1,242 @@
+use crate::{ReadAt, WriteAt};
+use std::{
+    io,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// A write operation recorded by a [`RecordingWriter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteOp {
+    /// A call to [`write_at`](WriteAt::write_at).
+    Write {
+        /// The offset of the write.
+        offset: u64,
+        /// The bytes written.
+        data: Vec<u8>,
+    },
+    /// A call to [`write_all_at`](WriteAt::write_all_at).
+    WriteAll {
+        /// The offset of the write.
+        offset: u64,
+        /// The bytes written.
+        data: Vec<u8>,
+    },
+    /// A call to [`write_vectored_at`](WriteAt::write_vectored_at).
+    WriteVectored {
+        /// The offset of the write.
+        offset: u64,
+        /// The bytes written, concatenated.
+        data: Vec<u8>,
+    },
+    /// A call to [`flush`](WriteAt::flush).
+    Flush,
+    /// A call to [`flush_tracked`](WriteAt::flush_tracked).
+    FlushTracked,
+}
+
+impl WriteOp {
+    /// Returns the offset of the operation, or `None` for a flush.
+    #[must_use]
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Write { offset, .. }
+            | Self::WriteAll { offset, .. }
+            | Self::WriteVectored { offset, .. } => Some(*offset),
+            Self::Flush | Self::FlushTracked => None,
+        }
+    }
+
+    /// Returns the bytes written by the operation, which are empty for a
+    /// flush.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        match self {
+            Self::Write { data, .. }
+            | Self::WriteAll { data, .. }
+            | Self::WriteVectored { data, .. } => data,
+            Self::Flush | Self::FlushTracked => &[],
+        }
+    }
+}
+
+/// A writer that records the operations made through it.
+///
+/// This is a test double: each successful call is forwarded to the inner
+/// writer, then recorded as a [`WriteOp`] with the bytes the inner writer
+/// accepted. Failed calls are not recorded. The default inner writer is
+/// [`io::Sink`], which accepts everything, and the recorded operations can be
+/// [replayed](Self::replay) to another writer later.
+#[derive(Debug)]
+pub struct RecordingWriter<W = io::Sink> {
+    inner: W,
+    ops: Mutex<Vec<WriteOp>>,
+}
+
+impl<W> RecordingWriter<W> {
+    /// Creates a new `RecordingWriter` forwarding writes to `inner`.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            ops: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns a copy of the operations recorded so far, in order.
+    #[must_use]
+    pub fn operations(&self) -> Vec<WriteOp> {
+        self.lock().clone()
+    }
+
+    /// Clears the recorded operations.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Writes the recorded operations to `target`, in order.
+    ///
+    /// Each write is replayed with [`write_all_at`](WriteAt::write_all_at),
+    /// and each flush with the same method as the recorded one.
+    pub fn replay<T: WriteAt + ?Sized>(&self, target: &T) -> io::Result<()> {
+        for op in self.lock().iter() {
+            match op {
+                WriteOp::Write { offset, data }
+                | WriteOp::WriteAll { offset, data }
+                | WriteOp::WriteVectored { offset, data } => target.write_all_at(data, *offset)?,
+                WriteOp::Flush => target.flush()?,
+                WriteOp::FlushTracked => {
+                    target.flush_tracked()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer, discarding the recorded operations.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Vec<WriteOp>> {
+        self.ops.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for RecordingWriter {
+    /// Creates a new `RecordingWriter` that only records operations.
+    #[inline]
+    fn default() -> Self {
+        Self::new(io::sink())
+    }
+}
+
+impl<W: WriteAt> WriteAt for RecordingWriter<W> {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let n = self.inner.write_at(buf, offset)?;
+        self.lock().push(WriteOp::Write {
+            offset,
+            data: buf[..n].to_vec(),
+        });
+        Ok(n)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.inner.write_all_at(buf, offset)?;
+        self.lock().push(WriteOp::WriteAll {
+            offset,
+            data: buf.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        let n = self.inner.write_vectored_at(bufs, offset)?;
+        let mut data = Vec::with_capacity(n);
+        for buf in bufs {
+            let len = buf.len().min(n - data.len());
+            data.extend_from_slice(&buf[..len]);
+        }
+        self.lock().push(WriteOp::WriteVectored { offset, data });
+        Ok(n)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.lock().push(WriteOp::Flush);
+        Ok(())
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let durable = self.inner.flush_tracked()?;
+        self.lock().push(WriteOp::FlushTracked);
+        Ok(durable)
+    }
+}
+
+/// Reads are forwarded to the inner writer, and are not recorded.
+impl<W: ReadAt> ReadAt for RecordingWriter<W> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn records_operations() {
+        let writer = RecordingWriter::default();
+        writer.write_all_at(b"Hello", 0).unwrap();
+        writer.write_at(b"World", 6).unwrap();
+        let bufs = [io::IoSlice::new(b" "), io::IoSlice::new(b"!")];
+        writer.write_vectored_at(&bufs, 11).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.flush_tracked().unwrap(), 0);
+
+        let ops = writer.operations();
+        assert_eq!(
+            ops,
+            [
+                WriteOp::WriteAll {
+                    offset: 0,
+                    data: b"Hello".to_vec()
+                },
+                WriteOp::Write {
+                    offset: 6,
+                    data: b"World".to_vec()
+                },
+                WriteOp::WriteVectored {
+                    offset: 11,
+                    data: b" !".to_vec()
+                },
+                WriteOp::Flush,
+                WriteOp::FlushTracked,
+            ]
+        );
+        let offsets: Vec<_> = ops.iter().map(WriteOp::offset).collect();
+        assert_eq!(offsets, [Some(0), Some(6), Some(11), None, None]);
+
+        let target = MemFile::default();
+        writer.replay(&target).unwrap();
+        assert_eq!(target.contents(), b"Hello\0World !");
+
+        writer.clear();
+        assert!(writer.operations().is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{range_map::RangeMap, WriteAt};
+use std::{
+    io,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// A writer that buffers writes and issues them sorted by offset.
+///
+/// Writes are kept in memory, where overlapping and adjacent ones are merged,
+/// until [`flush`](WriteAt::flush) is called or until they would exceed the
+/// memory limit given to [`new`](Self::new). They are then written to the
+/// inner writer in offset order, which turns random writes into sequential
+/// ones. A single write larger than the limit is written directly, after the
+/// buffered ones.
+///
+/// After `flush` returns successfully, all buffered writes have been written
+/// to the inner writer, then the inner writer has been flushed. If it fails,
+/// buffered writes are kept, and the next flush writes them again. Writes
+/// that are still buffered when the `Reordering` writer is dropped are lost,
+/// and reading the inner writer does not see them.
+#[derive(Debug)]
+pub struct Reordering<W> {
+    inner: W,
+    limit: usize,
+    pending: Mutex<RangeMap>,
+}
+
+impl<W> Reordering<W> {
+    /// Creates a new `Reordering` writer buffering at most `limit` bytes.
+    #[inline]
+    pub fn new(inner: W, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            pending: Mutex::new(RangeMap::new()),
+        }
+    }
+
+    /// Returns the number of bytes currently buffered.
+    #[must_use]
+    pub fn pending_bytes(&self) -> usize {
+        self.lock().bytes()
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer, discarding buffered writes.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, RangeMap> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: WriteAt> Reordering<W> {
+    /// Writes buffered ranges to the inner writer, in offset order.
+    fn write_pending(&self, pending: &mut RangeMap) -> io::Result<()> {
+        for (offset, data) in pending.iter() {
+            self.inner.write_all_at(data, offset)?;
+        }
+        pending.clear();
+        Ok(())
+    }
+}
+
+impl<W: WriteAt> WriteAt for Reordering<W> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        if offset.checked_add(buf.len() as u64).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "write past the maximum offset",
+            ));
+        }
+
+        let mut pending = self.lock();
+        if pending.bytes_after_insert(offset, buf.len()) > self.limit {
+            self.write_pending(&mut pending)?;
+            if buf.len() > self.limit {
+                return self.inner.write_all_at(buf, offset);
+            }
+        }
+        pending.insert(offset, buf);
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let mut pending = self.lock();
+        self.write_pending(&mut pending)?;
+        self.inner.flush()
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let mut pending = self.lock();
+        self.write_pending(&mut pending)?;
+        self.inner.flush_tracked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::MemFile, RecordingWriter, WriteOp};
+
+    fn write_all(offset: u64, data: &[u8]) -> WriteOp {
+        WriteOp::WriteAll {
+            offset,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn sorted_writes() {
+        let writer = Reordering::new(RecordingWriter::new(MemFile::default()), 16);
+        writer.write_all_at(b"World", 6).unwrap();
+        writer.write_all_at(b"!", 11).unwrap();
+        writer.write_all_at(b"Hello ", 0).unwrap();
+        writer.write_all_at(b"Rust", 20).unwrap();
+        assert!(writer.get_ref().operations().is_empty());
+        assert_eq!(writer.pending_bytes(), 16);
+
+        writer.flush().unwrap();
+        assert_eq!(
+            writer.get_ref().operations(),
+            [
+                write_all(0, b"Hello World!"),
+                write_all(20, b"Rust"),
+                WriteOp::Flush,
+            ]
+        );
+        assert_eq!(
+            writer.get_ref().get_ref().contents(),
+            b"Hello World!\0\0\0\0\0\0\0\0Rust"
+        );
+    }
+
+    #[test]
+    fn memory_limit() {
+        let writer = Reordering::new(RecordingWriter::new(MemFile::default()), 8);
+        writer.write_all_at(b"World", 6).unwrap();
+        writer.write_all_at(b"Hello", 0).unwrap();
+        assert_eq!(writer.get_ref().operations(), [write_all(6, b"World")]);
+
+        writer.write_all_at(b"0123456789", 20).unwrap();
+        assert_eq!(
+            writer.get_ref().operations(),
+            [
+                write_all(6, b"World"),
+                write_all(0, b"Hello"),
+                write_all(20, b"0123456789"),
+            ]
+        );
+        assert_eq!(writer.pending_bytes(), 0);
+    }
+}
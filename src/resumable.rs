@@ -0,0 +1,243 @@
+use crate::{crc32::crc32, RangeSet, ReadAt, WriteAt};
+use std::{
+    convert::TryInto,
+    io,
+    ops::Range,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+const COUNT_LEN: usize = 8;
+const RANGE_LEN: usize = 16;
+const CHECKSUM_LEN: usize = 4;
+/// The size of the chunks in which the sidecar is read, so that a corrupted
+/// count does not allocate more than the sidecar holds.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// A writer that records which ranges were written in a sidecar target.
+///
+/// This is meant for downloads that can be interrupted: after a restart,
+/// [`open`](Self::open) loads the ranges from the sidecar, and
+/// [`missing_ranges`](Self::missing_ranges) gives the ones that are still to
+/// be downloaded.
+///
+/// A range is only recorded once [`write_all_at`](WriteAt::write_all_at)
+/// returned successfully. Durability relies on the
+/// [`flush_tracked`](WriteAt::flush_tracked) method of the targets, which
+/// syncs a [`RandomAccessFile`](crate::RandomAccessFile) to disk: the data is
+/// synced before the range is recorded, and the sidecar is synced before
+/// `write_all_at` returns, so both targets must support it.
+///
+/// # Format
+///
+/// The sidecar contains a [`RangeSet`] serialized with
+/// [`to_bytes`](RangeSet::to_bytes), followed by its CRC-32 as a
+/// little-endian `u32`. It is rewritten in place after each write, so an
+/// interrupted update may leave it corrupted, in which case `open` fails.
+#[derive(Debug)]
+pub struct ResumableWriter<W, S = W> {
+    inner: W,
+    sidecar: S,
+    completed: Mutex<RangeSet>,
+}
+
+impl<W, S: ReadAt> ResumableWriter<W, S> {
+    /// Opens a resumable writer, loading the ranges recorded in the sidecar.
+    ///
+    /// An empty sidecar means that nothing was written yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the sidecar
+    /// is corrupted.
+    pub fn open(inner: W, sidecar: S) -> io::Result<Self> {
+        let completed = load(&sidecar)?;
+        Ok(Self {
+            inner,
+            sidecar,
+            completed: Mutex::new(completed),
+        })
+    }
+}
+
+impl<W, S> ResumableWriter<W, S> {
+    /// Returns the ranges that were written.
+    #[must_use]
+    pub fn completed(&self) -> RangeSet {
+        self.lock().clone()
+    }
+
+    /// Returns the ranges of `0..total_len` that were not written yet, in
+    /// order.
+    #[must_use]
+    pub fn missing_ranges(&self, total_len: u64) -> Vec<Range<u64>> {
+        self.lock().gaps(0..total_len).collect()
+    }
+
+    /// Returns `true` if all bytes of `0..total_len` were written.
+    #[must_use]
+    pub fn is_complete(&self, total_len: u64) -> bool {
+        self.lock().contains_range(0..total_len)
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a reference to the sidecar.
+    #[inline]
+    pub fn sidecar(&self) -> &S {
+        &self.sidecar
+    }
+
+    /// Unwraps the underlying writer and the sidecar.
+    #[inline]
+    pub fn into_inner(self) -> (W, S) {
+        (self.inner, self.sidecar)
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, RangeSet> {
+        self.completed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: WriteAt, S: WriteAt> WriteAt for ResumableWriter<W, S> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let end = offset.checked_add(buf.len() as u64).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "write past the maximum offset")
+        })?;
+
+        self.inner.write_all_at(buf, offset)?;
+        self.inner.flush_tracked()?;
+
+        let mut completed = self.lock();
+        completed.insert(offset..end);
+        let mut bytes = completed.to_bytes();
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        self.sidecar.write_all_at(&bytes, 0)?;
+        self.sidecar.flush_tracked()?;
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<W: ReadAt, S> ReadAt for ResumableWriter<W, S> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+/// Reads the ranges recorded in a sidecar.
+fn load<S: ReadAt>(sidecar: &S) -> io::Result<RangeSet> {
+    let mut count = [0; COUNT_LEN];
+    match sidecar.read_exact_at(&mut count, 0) {
+        Ok(()) => (),
+        // An empty sidecar has no recorded range.
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(RangeSet::new()),
+        Err(e) => return Err(e),
+    }
+
+    let len = u64::from_le_bytes(count)
+        .checked_mul(RANGE_LEN as u64)
+        .and_then(|len| len.checked_add((COUNT_LEN + CHECKSUM_LEN) as u64))
+        .and_then(|len| usize::try_from(len).ok())
+        .ok_or_else(corrupted_sidecar)?;
+    let mut bytes = Vec::new();
+    while bytes.len() < len {
+        let start = bytes.len();
+        bytes.resize(start + (len - start).min(READ_CHUNK), 0);
+        sidecar
+            .read_exact_at(&mut bytes[start..], start as u64)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => corrupted_sidecar(),
+                _ => e,
+            })?;
+    }
+
+    let (set, checksum) = bytes.split_at(len - CHECKSUM_LEN);
+    if crc32(set) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err(corrupted_sidecar());
+    }
+    RangeSet::from_bytes(set)
+}
+
+#[cold]
+fn corrupted_sidecar() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupted sidecar")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn resume() {
+        let writer = ResumableWriter::open(MemFile::default(), MemFile::default()).unwrap();
+        assert_eq!(writer.missing_ranges(20), vec![0..20]);
+
+        writer.write_all_at(b"Hello", 0).unwrap();
+        writer.write_all_at(b"World", 10).unwrap();
+        writer.write_all_at(b"!", 15).unwrap();
+        writer.write_all_at(b" ", 5).unwrap();
+        let (data, sidecar) = writer.into_inner();
+
+        // Restart from the same targets.
+        let writer = ResumableWriter::open(data, sidecar).unwrap();
+        assert_eq!(writer.missing_ranges(20), [6..10, 16..20]);
+        assert!(!writer.is_complete(20));
+
+        writer.write_all_at(b"Rust", 6).unwrap();
+        writer.write_all_at(b"1234", 16).unwrap();
+        assert!(writer.is_complete(20));
+        assert_eq!(writer.get_ref().contents(), b"Hello RustWorld!1234");
+
+        let (data, sidecar) = writer.into_inner();
+        let writer = ResumableWriter::open(data, sidecar).unwrap();
+        assert_eq!(writer.completed().iter().collect::<Vec<_>>(), vec![0..20]);
+    }
+
+    #[test]
+    fn corrupted_sidecar() {
+        let writer = ResumableWriter::open(MemFile::default(), MemFile::default()).unwrap();
+        writer.write_all_at(b"Hello", 0).unwrap();
+        let (data, sidecar) = writer.into_inner();
+
+        sidecar.write_all_at(&[1], 12).unwrap();
+        let err = ResumableWriter::open(&data, &sidecar).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A huge count is not trusted to allocate memory.
+        sidecar
+            .write_all_at(&(1u64 << 40).to_le_bytes(), 0)
+            .unwrap();
+        let err = ResumableWriter::open(&data, &sidecar).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
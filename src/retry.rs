@@ -0,0 +1,185 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::io;
+
+/// A wrapper that retries interrupted operations.
+///
+/// Files return errors of kind [`io::ErrorKind::Interrupted`] to the caller,
+/// like [`File`](std::fs::File) does, while in-memory sources are never
+/// interrupted. This wrapper retries every operation of the inner source
+/// that fails with such an error, so that generic code sees the same
+/// behavior for both.
+///
+/// Retrying [`read_exact_at`](ReadAt::read_exact_at) or
+/// [`write_all_at`](WriteAt::write_all_at) transfers the whole buffer again,
+/// which is harmless as positional operations do not depend on previous ones.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetryInterrupted<T> {
+    inner: T,
+}
+
+impl<T> RetryInterrupted<T> {
+    /// Creates a new `RetryInterrupted` wrapper.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Calls `f` again while it fails with `Interrupted`.
+#[inline]
+fn retry<T>(mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    loop {
+        match f() {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            res => return res,
+        }
+    }
+}
+
+impl<T: ReadAt> ReadAt for RetryInterrupted<T> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        retry(|| self.inner.read_at(buf, offset))
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        retry(|| self.inner.read_exact_at(buf, offset))
+    }
+
+    #[inline]
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        retry(|| self.inner.read_vectored_at(bufs, offset))
+    }
+}
+
+impl<T: WriteAt> WriteAt for RetryInterrupted<T> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        retry(|| self.inner.write_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        retry(|| self.inner.write_all_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        retry(|| self.inner.write_vectored_at(bufs, offset))
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        retry(|| self.inner.flush())
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        retry(|| self.inner.flush_tracked())
+    }
+}
+
+impl<T: Size> Size for RetryInterrupted<T> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        retry(|| self.inner.size())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+    use std::cell::Cell;
+
+    /// A file that is interrupted every other call.
+    #[derive(Default)]
+    struct Interrupting {
+        file: MemFile,
+        interrupt: Cell<bool>,
+    }
+
+    impl Interrupting {
+        fn check(&self) -> io::Result<()> {
+            let interrupt = !self.interrupt.get();
+            self.interrupt.set(interrupt);
+            match interrupt {
+                true => Err(io::ErrorKind::Interrupted.into()),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl ReadAt for Interrupting {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            self.check()?;
+            self.file.read_at(buf, offset)
+        }
+
+        fn read_vectored_at(
+            &self,
+            bufs: &mut [io::IoSliceMut<'_>],
+            offset: u64,
+        ) -> io::Result<usize> {
+            self.check()?;
+            self.file.read_vectored_at(bufs, offset)
+        }
+    }
+
+    impl WriteAt for Interrupting {
+        fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+            self.check()?;
+            self.file.write_at(buf, offset)
+        }
+
+        fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+            self.check()?;
+            self.file.write_vectored_at(bufs, offset)
+        }
+
+        fn flush(&self) -> io::Result<()> {
+            self.check()
+        }
+    }
+
+    #[test]
+    fn retries() {
+        let file = Interrupting::default();
+        let err = file.write_at(b"Hello", 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        let file = RetryInterrupted::new(file);
+        assert_eq!(file.write_at(b"Hello", 0).unwrap(), 5);
+        let bufs = [io::IoSlice::new(b" ")];
+        assert_eq!(file.write_vectored_at(&bufs, 5).unwrap(), 1);
+        file.write_all_at(b"World!", 6).unwrap();
+        file.flush().unwrap();
+
+        let mut buf = [0; 5];
+        assert_eq!(file.read_at(&mut buf, 6).unwrap(), 5);
+        assert_eq!(&buf, b"World");
+        let mut bufs = [io::IoSliceMut::new(&mut buf)];
+        assert_eq!(file.read_vectored_at(&mut bufs, 0).unwrap(), 5);
+        assert_eq!(&buf, b"Hello");
+        file.read_exact_at(&mut buf, 7).unwrap();
+        assert_eq!(&buf, b"orld!");
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{ReadAt, Size};
+use std::{fmt, io};
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+
+const TAG_LEN: usize = 16;
+
+/// Computes the nonce of a chunk from the nonce prefix and its index.
+fn nonce(prefix: &[u8; 4], index: u64) -> [u8; 12] {
+    let mut nonce = [0; 12];
+    nonce[..4].copy_from_slice(prefix);
+    nonce[4..].copy_from_slice(&index.to_le_bytes());
+    nonce
+}
+
+/// A writer that seals data in authenticated chunks with ChaCha20-Poly1305.
+///
+/// Data is split into chunks of a fixed plaintext size, which are encrypted
+/// and authenticated separately, so that [`SealedReader`] can decrypt any of
+/// them without the others.
+///
+/// # Framing
+///
+/// Each chunk is stored as its ciphertext followed by a 16-byte tag, so the
+/// chunk `i` starts at offset `i * (chunk_size + 16)`. All chunks are full
+/// except the last one, which may be shorter or even empty.
+///
+/// The nonce of chunk `i` is the 4-byte nonce prefix followed by `i` as a
+/// little-endian `u64`. The associated data is a single byte, which is `1` for
+/// the last chunk and `0` for the others, so that truncating the data is
+/// detected.
+///
+/// The same key and nonce prefix must never be used to seal two different
+/// streams, as this would reuse nonces.
+pub struct SealedWriter<W> {
+    inner: W,
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; 4],
+    chunk_size: usize,
+    buf: Vec<u8>,
+    index: u64,
+}
+
+impl<W: io::Write> SealedWriter<W> {
+    /// Creates a new `SealedWriter`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn new(inner: W, key: &[u8; 32], nonce_prefix: [u8; 4], chunk_size: usize) -> Self {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+
+        Self {
+            inner,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            nonce_prefix,
+            chunk_size,
+            buf: Vec::with_capacity(chunk_size + TAG_LEN),
+            index: 0,
+        }
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = nonce(&self.nonce_prefix, self.index);
+        let payload = Payload {
+            msg: &self.buf,
+            aad: &[last as u8],
+        };
+        let sealed = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "failed to seal chunk"))?;
+
+        self.inner.write_all(&sealed)?;
+        self.buf.clear();
+        self.index += 1;
+        Ok(())
+    }
+
+    /// Seals the last chunk, and returns the underlying writer.
+    ///
+    /// This must be called, or the data will be considered truncated.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W> SealedWriter<W> {
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: io::Write> io::Write for SealedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // A full chunk is only sealed when more data comes, as we don't know
+        // whether it is the last one before.
+        if self.buf.len() == self.chunk_size {
+            self.seal_chunk(false)?;
+        }
+
+        let len = buf.len().min(self.chunk_size - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    /// Flushes the underlying writer.
+    ///
+    /// The current chunk is not sealed until it is full or
+    /// [`finish`](SealedWriter::finish) is called.
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for SealedWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealedWriter")
+            .field("inner", &self.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A reader that decrypts and verifies data sealed by a [`SealedWriter`].
+///
+/// Each read decrypts the chunk covering its offset, and fails with
+/// [`io::ErrorKind::InvalidData`] if it was tampered with. See
+/// [`SealedWriter`] for the chunk framing.
+pub struct SealedReader<R> {
+    inner: R,
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; 4],
+    chunk_size: u64,
+    chunks: u64,
+    last_len: u64,
+    size: u64,
+}
+
+impl<R> SealedReader<R>
+where
+    R: ReadAt + Size,
+{
+    /// Opens sealed data.
+    ///
+    /// The key, nonce prefix and chunk size must be the ones used to seal it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the size of
+    /// the data does not match the framing.
+    pub fn new(
+        inner: R,
+        key: &[u8; 32],
+        nonce_prefix: [u8; 4],
+        chunk_size: usize,
+    ) -> io::Result<Self> {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+
+        let sealed_size = inner.size()?;
+        let stride = chunk_size as u64 + TAG_LEN as u64;
+        if sealed_size == 0 {
+            return Err(invalid_data("invalid sealed data length"));
+        }
+        let chunks = (sealed_size - 1) / stride + 1;
+        let last_len = sealed_size - (chunks - 1) * stride;
+        if last_len < TAG_LEN as u64 {
+            return Err(invalid_data("invalid sealed data length"));
+        }
+
+        Ok(Self {
+            inner,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            nonce_prefix,
+            chunk_size: chunk_size as u64,
+            chunks,
+            last_len,
+            size: sealed_size - chunks * TAG_LEN as u64,
+        })
+    }
+}
+
+impl<R> SealedReader<R> {
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ReadAt> ReadAt for SealedReader<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let index = offset / self.chunk_size;
+        let in_chunk = (offset % self.chunk_size) as usize;
+        let last = index == self.chunks - 1;
+        let sealed_len = if last {
+            self.last_len
+        } else {
+            self.chunk_size + TAG_LEN as u64
+        };
+
+        let mut sealed = vec![0; sealed_len as usize];
+        self.inner
+            .read_exact_at(&mut sealed, index * (self.chunk_size + TAG_LEN as u64))?;
+
+        let nonce = nonce(&self.nonce_prefix, index);
+        let payload = Payload {
+            msg: &sealed,
+            aad: &[last as u8],
+        };
+        let chunk = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| invalid_data("chunk authentication failed"))?;
+
+        chunk[in_chunk..].read_at(buf, 0)
+    }
+}
+
+impl<R> Size for SealedReader<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.size)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.size)
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for SealedReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealedReader")
+            .field("inner", &self.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cold]
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const KEY: [u8; 32] = [42; 32];
+    const PREFIX: [u8; 4] = *b"test";
+
+    fn seal(data: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut writer = SealedWriter::new(Vec::new(), &KEY, PREFIX, chunk_size);
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let sealed = seal(&data, 64);
+        assert_eq!(sealed.len(), 256 + 4 * TAG_LEN);
+
+        let reader = SealedReader::new(sealed, &KEY, PREFIX, 64).unwrap();
+        assert_eq!(reader.size().unwrap(), 256);
+
+        let mut buf = [0; 100];
+        reader.read_exact_at(&mut buf, 100).unwrap();
+        assert_eq!(buf[..], data[100..200]);
+        assert_eq!(reader.read_at(&mut buf, 256).unwrap(), 0);
+
+        let empty = SealedReader::new(seal(b"", 64), &KEY, PREFIX, 64).unwrap();
+        assert_eq!(empty.size().unwrap(), 0);
+    }
+
+    #[test]
+    fn tampered_chunk() {
+        let mut sealed = seal(&[7; 200], 64);
+        sealed[90] ^= 1;
+        let reader = SealedReader::new(sealed, &KEY, PREFIX, 64).unwrap();
+
+        let mut buf = [0; 10];
+        reader.read_exact_at(&mut buf, 0).unwrap();
+        let err = reader.read_exact_at(&mut buf, 70).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_data() {
+        let mut sealed = seal(&[7; 200], 64);
+        sealed.truncate(2 * (64 + TAG_LEN));
+        let reader = SealedReader::new(sealed, &KEY, PREFIX, 64).unwrap();
+
+        let mut buf = [0; 10];
+        let err = reader.read_exact_at(&mut buf, 64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
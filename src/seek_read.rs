@@ -0,0 +1,96 @@
+use crate::{ReadAt, Size};
+use std::{
+    io::{self, Read, Seek},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// An adapter that implements [`ReadAt`] for any reader that can seek.
+///
+/// Each read locks an internal mutex, seeks to the requested offset and reads
+/// from there. This is what [`RandomAccessFile`] does on platforms without
+/// positional I/O, generalized to any `Read + Seek` type.
+///
+/// Because of the mutex, concurrent reads are serialized, and each one costs a
+/// seek in addition to the read itself.
+///
+/// [`RandomAccessFile`]: crate::RandomAccessFile
+#[derive(Debug, Default)]
+pub struct SeekReadAt<T>(Mutex<T>);
+
+impl<T> SeekReadAt<T> {
+    /// Creates a new `SeekReadAt`.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self(Mutex::new(inner))
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Unwraps the underlying reader.
+    ///
+    /// Its cursor position is unspecified.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> ReadAt for SeekReadAt<T>
+where
+    T: Read + Seek,
+{
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let inner = &mut *self.lock();
+        inner.seek(io::SeekFrom::Start(offset))?;
+        inner.read(buf)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let inner = &mut *self.lock();
+        inner.seek(io::SeekFrom::Start(offset))?;
+        inner.read_exact(buf)
+    }
+
+    fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        let inner = &mut *self.lock();
+        inner.seek(io::SeekFrom::Start(offset))?;
+        inner.read_vectored(bufs)
+    }
+}
+
+impl<T> Size for SeekReadAt<T>
+where
+    T: Seek,
+{
+    fn size(&self) -> io::Result<u64> {
+        self.lock().seek(io::SeekFrom::End(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_order_reads() {
+        let reader = SeekReadAt::new(io::Cursor::new(b"Hello World!".to_vec()));
+        let mut buf = [0; 5];
+
+        reader.read_exact_at(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+        reader.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello");
+        assert_eq!(reader.read_at(&mut buf, 10).unwrap(), 2);
+        assert_eq!(&buf[..2], b"d!");
+        assert_eq!(reader.size().unwrap(), 12);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{fmt, io};
+
+/// A writer that frees space and retries when the storage is full.
+///
+/// When a write fails because there is no space left on the device, the
+/// `free_space` callback is called, typically to evict entries from a cache,
+/// then the write is retried, up to a maximum number of retries. If the
+/// callback fails, its error is returned, and other errors are returned as
+/// is.
+///
+/// Retrying [`write_all_at`](WriteAt::write_all_at) writes the whole buffer
+/// again, including the bytes that were written before the failure, which is
+/// harmless as they are written at the same offsets.
+///
+/// The "no space" condition is detected from the raw OS error: `ENOSPC` on
+/// Unix and WASI, `ERROR_DISK_FULL` and `ERROR_HANDLE_DISK_FULL` on Windows.
+/// On other platforms, writes are never retried.
+pub struct SpaceRecovering<W, F> {
+    inner: W,
+    free_space: F,
+    max_retries: usize,
+}
+
+impl<W, F> SpaceRecovering<W, F>
+where
+    F: Fn() -> io::Result<()>,
+{
+    /// Creates a new `SpaceRecovering` writer, which retries each write once.
+    #[inline]
+    pub fn new(inner: W, free_space: F) -> Self {
+        Self::with_max_retries(inner, 1, free_space)
+    }
+
+    /// Creates a new `SpaceRecovering` writer, which retries each write up to
+    /// `max_retries` times.
+    #[inline]
+    pub fn with_max_retries(inner: W, max_retries: usize, free_space: F) -> Self {
+        Self {
+            inner,
+            free_space,
+            max_retries,
+        }
+    }
+
+    fn retry<T>(&self, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut retries = 0;
+        loop {
+            match op() {
+                Err(e) if retries < self.max_retries && is_storage_full(&e) => {
+                    (self.free_space)()?;
+                    retries += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+impl<W, F> SpaceRecovering<W, F> {
+    /// Returns the maximum number of retries of a write.
+    #[must_use]
+    #[inline]
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W, F> WriteAt for SpaceRecovering<W, F>
+where
+    W: WriteAt,
+    F: Fn() -> io::Result<()>,
+{
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.retry(|| self.inner.write_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.retry(|| self.inner.write_all_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        self.retry(|| self.inner.write_vectored_at(bufs, offset))
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.retry(|| self.inner.flush())
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.retry(|| self.inner.flush_tracked())
+    }
+}
+
+impl<W: ReadAt, F> ReadAt for SpaceRecovering<W, F> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<W: Size, F> Size for SpaceRecovering<W, F> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+impl<W: fmt::Debug, F> fmt::Debug for SpaceRecovering<W, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpaceRecovering")
+            .field("inner", &self.inner)
+            .field("max_retries", &self.max_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns whether `err` means that there is no space left on the device.
+fn is_storage_full(err: &io::Error) -> bool {
+    let code = match err.raw_os_error() {
+        Some(code) => code,
+        None => return false,
+    };
+
+    #[cfg(unix)]
+    {
+        code == libc::ENOSPC
+    }
+
+    #[cfg(target_os = "wasi")]
+    {
+        code == wasi::ERRNO_NOSPC.raw() as i32
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::{ERROR_DISK_FULL, ERROR_HANDLE_DISK_FULL};
+        code == ERROR_DISK_FULL as i32 || code == ERROR_HANDLE_DISK_FULL as i32
+    }
+
+    #[cfg(not(any(unix, target_os = "windows", target_os = "wasi")))]
+    {
+        let _ = code;
+        false
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// A writer that is full until space is freed.
+    #[derive(Default)]
+    struct Disk {
+        full: AtomicBool,
+        data: MemFile,
+    }
+
+    impl WriteAt for Disk {
+        fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+            if self.full.load(Ordering::SeqCst) {
+                return Err(io::Error::from_raw_os_error(libc::ENOSPC));
+            }
+            self.data.write_at(buf, offset)
+        }
+    }
+
+    #[test]
+    fn retry_after_freeing_space() {
+        let disk = Disk::default();
+        disk.full.store(true, Ordering::SeqCst);
+
+        let calls = AtomicUsize::new(0);
+        let writer = SpaceRecovering::new(&disk, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            disk.full.store(false, Ordering::SeqCst);
+            Ok(())
+        });
+        writer.write_all_at(b"Hello World!", 0).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(disk.data.contents(), b"Hello World!");
+    }
+
+    #[test]
+    fn retry_limit() {
+        let disk = Disk::default();
+        disk.full.store(true, Ordering::SeqCst);
+
+        let calls = AtomicUsize::new(0);
+        let writer = SpaceRecovering::with_max_retries(&disk, 3, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+        let err = writer.write_all_at(b"Hello World!", 0).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOSPC));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{RandomAccessFile, ReadAt, Size, WriteAt};
+use std::io;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Copies a file while preserving its holes.
+///
+/// Only the data regions of `src` are written to `dst`, which is expected to
+/// be empty and to read unwritten ranges as zeros, as files do. If `src` ends
+/// with a hole, a single zero byte is written at its end, so that `dst` has
+/// the same length.
+///
+/// Returns the logical length of the copy, that is the length of `src`.
+///
+/// Data regions are found with `SEEK_DATA` and `SEEK_HOLE` on Linux and
+/// Android. On other platforms, or if the filesystem does not support it,
+/// the whole file is copied.
+pub fn sparse_copy<W>(src: &RandomAccessFile, dst: &W) -> io::Result<u64>
+where
+    W: WriteAt + ?Sized,
+{
+    let len = src.size()?;
+    let mut end = 0;
+
+    for region in data_regions(src, len) {
+        let (start, region_end) = region?;
+        copy_range(src, dst, start, region_end)?;
+        end = region_end;
+    }
+
+    if end < len {
+        dst.write_all_at(&[0], len - 1)?;
+    }
+
+    Ok(len)
+}
+
+fn copy_range<W>(src: &RandomAccessFile, dst: &W, mut start: u64, end: u64) -> io::Result<()>
+where
+    W: WriteAt + ?Sized,
+{
+    let mut buf = vec![0; CHUNK_SIZE.min((end - start) as usize)];
+    while start < end {
+        let len = buf.len().min((end - start) as usize);
+        let buf = &mut buf[..len];
+        src.read_exact_at(buf, start)?;
+        dst.write_all_at(buf, start)?;
+        start += len as u64;
+    }
+    Ok(())
+}
+
+/// Returns an iterator over the data regions of `file`, up to `len`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn data_regions(
+    file: &RandomAccessFile,
+    len: u64,
+) -> impl Iterator<Item = io::Result<(u64, u64)>> + '_ {
+    use std::os::unix::io::AsRawFd;
+
+    let seek = move |offset: u64, whence| -> io::Result<Option<u64>> {
+        let offset = offset
+            .try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))?;
+        let ret = unsafe { libc::lseek(file.as_raw_fd(), offset, whence) };
+        if ret >= 0 {
+            return Ok(Some(ret as u64));
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            // There is no data after `offset`.
+            Some(libc::ENXIO) => Ok(Some(len)),
+            // Holes are not supported.
+            Some(libc::EINVAL) => Ok(None),
+            _ => Err(err),
+        }
+    };
+
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset >= len {
+            return None;
+        }
+
+        let res = (|| {
+            let start = match seek(offset, libc::SEEK_DATA)? {
+                Some(start) => start.min(len),
+                None => return Ok((offset, len)),
+            };
+            let end = match seek(start, libc::SEEK_HOLE)? {
+                Some(end) => end.min(len),
+                None => len,
+            };
+            Ok((start, end))
+        })();
+
+        match res {
+            Ok((start, end)) => {
+                offset = end;
+                (start < end).then_some(Ok((start, end)))
+            }
+            Err(e) => {
+                offset = len;
+                Some(Err(e))
+            }
+        }
+    })
+}
+
+/// Returns an iterator over the data regions of `file`, up to `len`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn data_regions(_: &RandomAccessFile, len: u64) -> impl Iterator<Item = io::Result<(u64, u64)>> {
+    (len != 0).then_some(Ok((0, len))).into_iter()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+    use std::os::unix::fs::MetadataExt;
+
+    #[test]
+    fn preserves_holes() {
+        const LEN: u64 = 16 << 20;
+
+        let src_path = TempPath::new("sparse_copy_src");
+        let src = RandomAccessFile::from(src_path.create_rw());
+        src.set_len(LEN).unwrap();
+        src.write_all_at(b"Hello World!", 8 << 20).unwrap();
+
+        let dst_path = TempPath::new("sparse_copy_dst");
+        let dst = RandomAccessFile::from(dst_path.create_rw());
+        assert_eq!(sparse_copy(&src, &dst).unwrap(), LEN);
+
+        let metadata = dst.metadata().unwrap();
+        assert_eq!(metadata.len(), LEN);
+        assert!(metadata.blocks() * 512 < LEN);
+
+        let mut buf = [1; 12];
+        dst.read_exact_at(&mut buf, 8 << 20).unwrap();
+        assert_eq!(&buf, b"Hello World!");
+        dst.read_exact_at(&mut buf, 1 << 20).unwrap();
+        assert_eq!(buf, [0; 12]);
+    }
+}
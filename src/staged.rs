@@ -0,0 +1,225 @@
+use crate::{range_map::RangeMap, ReadAt, WriteAt};
+use std::{
+    io,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// A writer that keeps writes in memory until they are committed.
+///
+/// Writes to a `Staged` writer do not reach the target: they are kept in a
+/// sparse map of ranges, and reads through the `Staged` writer see them over
+/// the current contents of the target. On [`commit`](Self::commit), all
+/// staged ranges are written to the target in offset order, then the target
+/// is made durable with [`flush_tracked`](WriteAt::flush_tracked), which
+/// syncs a [`RandomAccessFile`](crate::RandomAccessFile) to disk. This gives
+/// "prepare then publish" semantics to a single writer, but the target may be
+/// left partially written if `commit` fails. Staged writes are rolled back
+/// with [`discard`](Self::discard).
+///
+/// Staged data never spills to disk. A memory limit can be set with
+/// [`with_limit`](Self::with_limit): writes that would make staged data
+/// exceed it fail with [`io::ErrorKind::OutOfMemory`], and can be retried
+/// after a commit.
+///
+/// [`flush`](WriteAt::flush) does not commit staged writes.
+#[derive(Debug)]
+pub struct Staged<W> {
+    inner: W,
+    limit: usize,
+    staged: Mutex<RangeMap>,
+}
+
+impl<W> Staged<W> {
+    /// Creates a new `Staged` writer without memory limit.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_limit(inner, usize::MAX)
+    }
+
+    /// Creates a new `Staged` writer keeping at most `limit` bytes in memory.
+    #[inline]
+    pub fn with_limit(inner: W, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            staged: Mutex::new(RangeMap::new()),
+        }
+    }
+
+    /// Returns the number of bytes currently staged.
+    #[must_use]
+    pub fn staged_bytes(&self) -> usize {
+        self.lock().bytes()
+    }
+
+    /// Discards all staged writes.
+    ///
+    /// This is the rollback of a transaction: the target is left as it was
+    /// after the last [`commit`](Staged::commit).
+    pub fn discard(&self) {
+        self.lock().clear();
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the underlying writer, discarding staged writes.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, RangeMap> {
+        self.staged.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: WriteAt> Staged<W> {
+    /// Writes all staged ranges to the target, then makes them durable with
+    /// [`flush_tracked`](WriteAt::flush_tracked).
+    ///
+    /// Returns the offset up to which the target is durable. Staged writes
+    /// are only discarded if this succeeds.
+    pub fn commit(&self) -> io::Result<u64> {
+        let mut staged = self.lock();
+        for (offset, data) in staged.iter() {
+            self.inner.write_all_at(data, offset)?;
+        }
+        let durable = self.inner.flush_tracked()?;
+        staged.clear();
+        Ok(durable)
+    }
+}
+
+impl<W: ReadAt> ReadAt for Staged<W> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let staged = self.lock();
+        let staged_end = match staged.end() {
+            Some(end) if end > offset => end,
+            _ => {
+                drop(staged);
+                return self.inner.read_at(buf, offset);
+            }
+        };
+
+        // Staged bytes may be past the end of the target, so short reads must
+        // be told apart from the end of file.
+        let mut read = 0;
+        while read < buf.len() {
+            match self.inner.read_at(&mut buf[read..], offset + read as u64) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        buf[read..].fill(0);
+
+        let len = read.max((staged_end - offset).min(buf.len() as u64) as usize);
+        staged.overlay(&mut buf[..len], offset);
+        Ok(len)
+    }
+}
+
+impl<W: WriteAt> WriteAt for Staged<W> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        if offset.checked_add(buf.len() as u64).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "write past the maximum offset",
+            ));
+        }
+
+        let mut staged = self.lock();
+        if staged.bytes_after_insert(offset, buf.len()) > self.limit {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                "staged data exceeds the memory limit",
+            ));
+        }
+        staged.insert(offset, buf);
+        Ok(())
+    }
+
+    /// Syncs the target, without committing staged writes.
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::{MemFile, TempPath},
+        Observable, RandomAccessFile,
+    };
+
+    #[test]
+    fn commit() {
+        let target = MemFile::default();
+        target.write_all_at(b"Hello World!", 0).unwrap();
+        let staged = Staged::new(target);
+
+        staged.write_all_at(b"Rust", 6).unwrap();
+        staged.write_all_at(b"!!", 14).unwrap();
+        assert_eq!(staged.get_ref().contents(), b"Hello World!");
+
+        let mut buf = [0xff; 16];
+        assert_eq!(staged.read_at(&mut buf, 0).unwrap(), 16);
+        assert_eq!(&buf, b"Hello Rustd!\0\0!!");
+
+        assert_eq!(staged.commit().unwrap(), 16);
+        assert_eq!(staged.staged_bytes(), 0);
+        assert_eq!(staged.get_ref().contents(), b"Hello Rustd!\0\0!!");
+    }
+
+    #[test]
+    fn commit_file() {
+        let path = TempPath::new("staged_commit_file");
+        let file = RandomAccessFile::from(path.create_rw());
+        file.write_all_at(b"Hello World!", 0).unwrap();
+        // Wrappers of the file forward `flush_tracked` to it.
+        let staged = Staged::new(Observable::new(file));
+
+        // Staged writes are visible through the `Staged` writer only.
+        staged.write_all_at(b"Rust!", 6).unwrap();
+        let mut buf = [0; 12];
+        staged.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello Rust!!");
+
+        staged.discard();
+        staged.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello World!");
+
+        staged.write_all_at(b"Rust", 6).unwrap();
+        staged.write_all_at(b"!!", 12).unwrap();
+        assert_eq!(staged.commit().unwrap(), 14);
+        assert_eq!(staged.staged_bytes(), 0);
+        assert_eq!(std::fs::read(&path).unwrap(), b"Hello Rustd!!!");
+    }
+
+    #[test]
+    fn memory_limit() {
+        let staged = Staged::with_limit(MemFile::default(), 7);
+        staged.write_all_at(b"Hello", 0).unwrap();
+        staged.write_all_at(b"World", 2).unwrap();
+
+        let err = staged.write_all_at(b"!", 7).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+
+        staged.commit().unwrap();
+        staged.write_all_at(b"!", 7).unwrap();
+    }
+}
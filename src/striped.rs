@@ -0,0 +1,210 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::io;
+
+/// A source striped across several devices, like RAID 0.
+///
+/// The logical source is split in stripes of `stripe_size` bytes, which are
+/// distributed to the devices in turn: the stripe `k` is stored on the device
+/// `k % n`, as the stripe `k / n` of this device, where `n` is the number of
+/// devices. Calls that cross stripe boundaries are split between devices.
+///
+/// The size of the source is the length of the longest prefix held by all
+/// devices. If a device is shorter than the layout requires, for example
+/// because a write failed halfway, bytes stored in other devices after its end
+/// are not counted, though they can still be read.
+#[derive(Debug)]
+pub struct Striped<R> {
+    devices: Vec<R>,
+    stripe_size: u64,
+}
+
+impl<R> Striped<R> {
+    /// Creates a new `Striped` source over `devices`, with stripes of
+    /// `stripe_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no devices or if `stripe_size` is zero.
+    pub fn new(devices: Vec<R>, stripe_size: u64) -> Self {
+        assert!(!devices.is_empty(), "no devices");
+        assert!(stripe_size != 0, "stripe size must be non-zero");
+
+        Self {
+            devices,
+            stripe_size,
+        }
+    }
+
+    /// Returns the size of stripes.
+    #[must_use]
+    #[inline]
+    pub fn stripe_size(&self) -> u64 {
+        self.stripe_size
+    }
+
+    /// Gets a reference to the underlying devices.
+    #[inline]
+    pub fn get_ref(&self) -> &[R] {
+        &self.devices
+    }
+
+    /// Unwraps the underlying devices.
+    #[inline]
+    pub fn into_inner(self) -> Vec<R> {
+        self.devices
+    }
+
+    /// Returns the device holding the logical `offset`, the offset in this
+    /// device, and the number of bytes left in the stripe.
+    fn locate(&self, offset: u64) -> (&R, u64, u64) {
+        let n = self.devices.len() as u64;
+        let stripe = offset / self.stripe_size;
+        let in_stripe = offset % self.stripe_size;
+
+        let device = &self.devices[(stripe % n) as usize];
+        let device_offset = stripe / n * self.stripe_size + in_stripe;
+        (device, device_offset, self.stripe_size - in_stripe)
+    }
+
+    /// Returns the first logical offset missing from the device `index`, if
+    /// its size is `size`.
+    fn first_missing(&self, index: usize, size: u64) -> u64 {
+        let n = self.devices.len() as u64;
+        let stripe = size / self.stripe_size * n + index as u64;
+        stripe
+            .saturating_mul(self.stripe_size)
+            .saturating_add(size % self.stripe_size)
+    }
+}
+
+impl<R: ReadAt> ReadAt for Striped<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let mut read = 0;
+
+        while read < buf.len() {
+            let (device, device_offset, left) = self.locate(offset + read as u64);
+            let len = left.min((buf.len() - read) as u64) as usize;
+
+            match device.read_at(&mut buf[read..read + len], device_offset) {
+                Ok(n) => {
+                    read += n;
+                    if n < len {
+                        break;
+                    }
+                }
+                Err(e) if read == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+
+        Ok(read)
+    }
+}
+
+impl<R: WriteAt> WriteAt for Striped<R> {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let mut written = 0;
+
+        while written < buf.len() {
+            let (device, device_offset, left) = self.locate(offset + written as u64);
+            let len = left.min((buf.len() - written) as u64) as usize;
+
+            match device.write_at(&buf[written..written + len], device_offset) {
+                Ok(n) => {
+                    written += n;
+                    if n < len {
+                        break;
+                    }
+                }
+                Err(e) if written == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        for device in &self.devices {
+            device.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let mut durable = u64::MAX;
+        for (index, device) in self.devices.iter().enumerate() {
+            durable = durable.min(self.first_missing(index, device.flush_tracked()?));
+        }
+        Ok(durable)
+    }
+}
+
+impl<R: Size> Size for Striped<R> {
+    fn size(&self) -> io::Result<u64> {
+        let mut size = u64::MAX;
+        for (index, device) in self.devices.iter().enumerate() {
+            size = size.min(self.first_missing(index, device.size()?));
+        }
+        Ok(size)
+    }
+
+    fn size_hint(&self) -> Option<u64> {
+        let mut size = u64::MAX;
+        for (index, device) in self.devices.iter().enumerate() {
+            size = size.min(self.first_missing(index, device.size_hint()?));
+        }
+        Some(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn read_across_stripes() {
+        // "Hello World!!" in stripes of 3 bytes.
+        let devices = vec![b"HelWor!".to_vec(), b"lo ld!".to_vec()];
+        let striped = Striped::new(devices, 3);
+        assert_eq!(striped.size().unwrap(), 13);
+
+        let mut buf = [0; 13];
+        striped.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello World!!");
+
+        let mut buf = [0; 7];
+        striped.read_exact_at(&mut buf, 2).unwrap();
+        assert_eq!(&buf, b"llo Wor");
+
+        assert_eq!(striped.read_at(&mut buf, 11).unwrap(), 2);
+        assert_eq!(&buf[..2], b"!!");
+    }
+
+    #[test]
+    fn round_trip() {
+        let payload: Vec<u8> = (0..100).collect();
+        let striped = Striped::new(vec![MemFile::default(), MemFile::default()], 8);
+        striped.write_all_at(&payload[..50], 0).unwrap();
+        striped.write_all_at(&payload[50..], 50).unwrap();
+        assert_eq!(striped.get_ref()[0].contents().len(), 52);
+        assert_eq!(striped.get_ref()[1].contents().len(), 48);
+        assert_eq!(striped.size().unwrap(), 100);
+
+        let mut buf = [0; 100];
+        striped.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(buf[..], payload[..]);
+    }
+
+    #[test]
+    fn mismatched_sizes() {
+        let striped = Striped::new(vec![MemFile::default(), MemFile::default()], 4);
+        striped.write_all_at(b"0123456789ab", 0).unwrap();
+        assert_eq!(striped.size().unwrap(), 12);
+
+        // The second stripe is missing: only the first one is counted.
+        let striped = Striped::new(vec![striped.get_ref()[0].contents(), Vec::new()], 4);
+        assert_eq!(striped.size().unwrap(), 4);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::WriteAt;
+use std::{fmt, io};
+
+/// A writer that mirrors writes to several targets.
+///
+/// Every write is issued to all targets at the same offset, in order. To keep
+/// targets consistent, `write_at` writes the whole buffer to each of them, as
+/// `write_all_at` would.
+///
+/// A write only succeeds if it succeeds for all targets. Otherwise the first
+/// error is returned immediately: earlier targets may already contain the
+/// data, while later ones were not written to.
+#[derive(Default)]
+pub struct TeeWriter {
+    targets: Vec<Box<dyn WriteAt + Send + Sync>>,
+}
+
+impl TeeWriter {
+    /// Creates a new `TeeWriter` without targets.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `TeeWriter` from a list of targets.
+    #[inline]
+    pub fn with_targets(targets: Vec<Box<dyn WriteAt + Send + Sync>>) -> Self {
+        Self { targets }
+    }
+
+    /// Adds a target, which will be written to after the existing ones.
+    #[inline]
+    pub fn push<W>(&mut self, target: W)
+    where
+        W: WriteAt + Send + Sync + 'static,
+    {
+        self.targets.push(Box::new(target));
+    }
+
+    /// Returns the targets of this writer.
+    #[inline]
+    pub fn targets(&self) -> &[Box<dyn WriteAt + Send + Sync>] {
+        &self.targets
+    }
+
+    /// Unwraps the targets of this writer.
+    #[inline]
+    pub fn into_targets(self) -> Vec<Box<dyn WriteAt + Send + Sync>> {
+        self.targets
+    }
+}
+
+impl WriteAt for TeeWriter {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_all_at(buf, offset)?;
+        Ok(buf.len())
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        for target in &self.targets {
+            target.write_all_at(buf, offset)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        for target in &self.targets {
+            target.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the smallest durable offset of the targets.
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let mut durable = u64::MAX;
+        for target in &self.targets {
+            durable = durable.min(target.flush_tracked()?);
+        }
+        Ok(durable)
+    }
+}
+
+impl fmt::Debug for TeeWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TeeWriter")
+            .field("targets", &self.targets.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+    use std::sync::Arc;
+
+    struct Failing;
+
+    impl WriteAt for Failing {
+        fn write_at(&self, _: &[u8], _: u64) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "failing target"))
+        }
+    }
+
+    #[test]
+    fn mirrors_writes() {
+        let (a, b) = (Arc::new(MemFile::default()), Arc::new(MemFile::default()));
+        let mut tee = TeeWriter::new();
+        tee.push(a.clone());
+        tee.push(b.clone());
+
+        assert_eq!(tee.write_at(b"World", 6).unwrap(), 5);
+        tee.write_all_at(b"Hello ", 0).unwrap();
+        tee.flush().unwrap();
+
+        assert_eq!(a.contents(), b"Hello World");
+        assert_eq!(b.contents(), b"Hello World");
+    }
+
+    #[test]
+    fn stops_on_first_error() {
+        let (a, b) = (Arc::new(MemFile::default()), Arc::new(MemFile::default()));
+        let mut tee = TeeWriter::new();
+        tee.push(a.clone());
+        tee.push(Failing);
+        tee.push(b.clone());
+
+        let err = tee.write_all_at(b"Hello", 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(a.contents(), b"Hello");
+        assert_eq!(b.contents(), b"");
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{RandomAccessFile, ReadAt, Size, WriteAt};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+/// A buffer kept in memory while small, which spills to a temporary file
+/// when it grows larger.
+///
+/// Data stays in memory until a write would extend the buffer beyond the
+/// spill threshold. The buffer is then moved to a new file in the temporary
+/// directory, which is used for all later I/O. This is transparent to
+/// callers: reads return the same data wherever it lives.
+///
+/// # Cleanup
+///
+/// On Unix, the temporary file is removed right after it is created, and only
+/// kept alive by its descriptor, so it is never left behind, even if the
+/// process is killed. On other platforms, it is removed when the buffer is
+/// dropped.
+///
+/// # Durability
+///
+/// The buffer is not meant to outlive the process, so
+/// [`flush`](WriteAt::flush) does nothing.
+#[derive(Debug)]
+pub struct TieredBuffer {
+    spill_threshold: u64,
+    temp_dir: PathBuf,
+    tier: RwLock<Tier>,
+}
+
+#[derive(Debug)]
+enum Tier {
+    Memory(Vec<u8>),
+    Disk {
+        file: RandomAccessFile,
+        /// The path to remove on drop, if the file was not already unlinked.
+        path: Option<PathBuf>,
+    },
+}
+
+impl TieredBuffer {
+    /// Creates an empty buffer which spills to a file in
+    /// [`std::env::temp_dir`] when it grows beyond `spill_threshold` bytes.
+    #[inline]
+    pub fn new(spill_threshold: u64) -> Self {
+        Self::with_temp_dir(spill_threshold, std::env::temp_dir())
+    }
+
+    /// Creates an empty buffer which spills to a file in `temp_dir` when it
+    /// grows beyond `spill_threshold` bytes.
+    pub fn with_temp_dir<P: AsRef<Path>>(spill_threshold: u64, temp_dir: P) -> Self {
+        Self {
+            spill_threshold,
+            temp_dir: temp_dir.as_ref().to_owned(),
+            tier: RwLock::new(Tier::Memory(Vec::new())),
+        }
+    }
+
+    /// Returns the size beyond which the buffer spills to disk.
+    #[must_use]
+    #[inline]
+    pub fn spill_threshold(&self) -> u64 {
+        self.spill_threshold
+    }
+
+    /// Returns `true` if the buffer was moved to a temporary file.
+    #[must_use]
+    pub fn is_spilled(&self) -> bool {
+        matches!(*self.read(), Tier::Disk { .. })
+    }
+
+    /// Moves the buffer to a new temporary file.
+    fn spill(&self, data: &[u8]) -> io::Result<Tier> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let (file, path) = loop {
+            let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let name = format!("sync_file-tiered-{}-{}", std::process::id(), n);
+            let path = self.temp_dir.join(name);
+
+            let file = fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path);
+            match file {
+                Ok(file) => break (RandomAccessFile::from(file), path),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        };
+
+        // Open files can be removed on Unix, which ensures that they are
+        // never left behind.
+        let path = if cfg!(unix) && fs::remove_file(&path).is_ok() {
+            None
+        } else {
+            Some(path)
+        };
+
+        // If the copy fails, dropping `tier` removes the file.
+        let tier = Tier::Disk { file, path };
+        if let Tier::Disk { file, .. } = &tier {
+            file.write_all_at(data, 0)?;
+        }
+        Ok(tier)
+    }
+
+    /// Writes to the memory buffer if the write stays within the threshold,
+    /// or else to the file, spilling the buffer first if needed.
+    fn write_with<T>(
+        &self,
+        buf: &[u8],
+        offset: u64,
+        written: T,
+        write_file: impl FnOnce(&RandomAccessFile) -> io::Result<T>,
+    ) -> io::Result<T> {
+        if let Tier::Disk { file, .. } = &*self.read() {
+            return write_file(file);
+        }
+
+        let mut tier = self.write();
+        if let Tier::Memory(data) = &mut *tier {
+            let end = offset
+                .checked_add(buf.len() as u64)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))?;
+
+            match usize::try_from(end) {
+                Ok(end) if end as u64 <= self.spill_threshold => {
+                    if data.len() < end {
+                        data.resize(end, 0);
+                    }
+                    data[offset as usize..end].copy_from_slice(buf);
+                    return Ok(written);
+                }
+                _ => *tier = self.spill(data)?,
+            }
+        }
+
+        match &*tier {
+            Tier::Disk { file, .. } => write_file(file),
+            Tier::Memory(_) => unreachable!(),
+        }
+    }
+
+    #[inline]
+    fn read(&self) -> RwLockReadGuard<'_, Tier> {
+        self.tier.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    fn write(&self) -> RwLockWriteGuard<'_, Tier> {
+        self.tier.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for Tier {
+    fn drop(&mut self) {
+        if let Tier::Disk {
+            path: Some(path), ..
+        } = self
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl ReadAt for TieredBuffer {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        match &*self.read() {
+            Tier::Memory(data) => data[..].read_at(buf, offset),
+            Tier::Disk { file, .. } => file.read_at(buf, offset),
+        }
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        match &*self.read() {
+            Tier::Memory(data) => data[..].read_exact_at(buf, offset),
+            Tier::Disk { file, .. } => file.read_exact_at(buf, offset),
+        }
+    }
+}
+
+impl Size for TieredBuffer {
+    fn size(&self) -> io::Result<u64> {
+        match &*self.read() {
+            Tier::Memory(data) => Ok(data.len() as u64),
+            Tier::Disk { file, .. } => file.size(),
+        }
+    }
+}
+
+impl WriteAt for TieredBuffer {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_with(buf, offset, buf.len(), |f| f.write_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.write_with(buf, offset, (), |f| f.write_all_at(buf, offset))
+    }
+
+    /// Does nothing, as the buffer is not meant to outlive the process.
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns the current size of the buffer, like other in-memory writers.
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempPath;
+
+    #[test]
+    fn spill_to_disk() {
+        let dir = TempPath::new("tiered");
+        fs::create_dir(&dir).unwrap();
+
+        let buffer = TieredBuffer::with_temp_dir(16, &dir);
+        buffer.write_all_at(b"Hello World!", 0).unwrap();
+        buffer.write_all_at(b"Rust", 12).unwrap();
+        assert!(!buffer.is_spilled());
+        assert_eq!(buffer.size().unwrap(), 16);
+
+        buffer.write_all_at(b"!!", 18).unwrap();
+        assert!(buffer.is_spilled());
+        assert_eq!(buffer.size().unwrap(), 20);
+
+        let mut buf = [0; 20];
+        buffer.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"Hello World!Rust\0\0!!");
+
+        buffer.write_all_at(b"Bye", 0).unwrap();
+        buffer.read_exact_at(&mut buf[..5], 0).unwrap();
+        assert_eq!(&buf[..5], b"Byelo");
+
+        drop(buffer);
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    io,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+const BUCKETS: usize = 64;
+
+/// Latency percentiles, as returned by [`Timed::latency_percentiles`].
+///
+/// Each value is the upper bound of the bucket holding the percentile, so
+/// it is at most twice the actual latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyPercentiles {
+    /// The median latency.
+    pub p50: Duration,
+    /// The 95th percentile.
+    pub p95: Duration,
+    /// The 99th percentile.
+    pub p99: Duration,
+}
+
+/// A wrapper that records the latency of reads and writes.
+///
+/// The duration of each call is counted in a histogram of log-scale buckets:
+/// the first one holds calls shorter than a microsecond, and the bucket `i`
+/// holds calls that took between `2^(i-1)` and `2^i` microseconds. Reads
+/// and writes share the same histogram, which is updated with atomics, so
+/// recording never blocks.
+#[derive(Debug)]
+pub struct Timed<R> {
+    inner: R,
+    buckets: [AtomicU64; BUCKETS],
+}
+
+impl<R> Timed<R> {
+    /// Creates a new `Timed` wrapper with an empty histogram.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns the number of recorded calls.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Returns the 50th, 95th and 99th percentiles of recorded latencies, or
+    /// `None` if no call was recorded.
+    #[must_use]
+    pub fn latency_percentiles(&self) -> Option<LatencyPercentiles> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let percentile = |q: f64| {
+            let rank = ((total as f64 * q).ceil() as u64).max(1);
+            let mut seen = 0;
+            let bucket = counts
+                .iter()
+                .position(|&count| {
+                    seen += count;
+                    seen >= rank
+                })
+                .unwrap_or(BUCKETS - 1);
+            Duration::from_micros(1 << bucket)
+        };
+
+        Some(LatencyPercentiles {
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        })
+    }
+
+    /// Clears the histogram.
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn time<T>(&self, op: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let res = op();
+        let micros = start.elapsed().as_micros();
+
+        let bucket = (128 - micros.leading_zeros() as usize).min(BUCKETS - 1);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        res
+    }
+}
+
+impl<R: ReadAt> ReadAt for Timed<R> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.time(|| self.inner.read_at(buf, offset))
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.time(|| self.inner.read_exact_at(buf, offset))
+    }
+}
+
+impl<R: WriteAt> WriteAt for Timed<R> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.time(|| self.inner.write_at(buf, offset))
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.time(|| self.inner.write_all_at(buf, offset))
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<R: Size> Size for Timed<R> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// A reader that sleeps for `offset` milliseconds.
+    struct Slow;
+
+    impl ReadAt for Slow {
+        fn read_at(&self, _: &mut [u8], offset: u64) -> io::Result<usize> {
+            thread::sleep(Duration::from_millis(offset));
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn percentiles() {
+        let reader = Timed::new(Slow);
+        assert_eq!(reader.latency_percentiles(), None);
+
+        for offset in [1, 1, 1, 1, 1, 1, 1, 1, 2, 20] {
+            reader.read_at(&mut [], offset).unwrap();
+        }
+        assert_eq!(reader.count(), 10);
+
+        let p = reader.latency_percentiles().unwrap();
+        assert!(p.p50 >= Duration::from_millis(1), "{p:?}");
+        assert!(p.p50 <= p.p95 && p.p95 <= p.p99, "{p:?}");
+        assert!(p.p99 >= Duration::from_millis(20), "{p:?}");
+
+        reader.reset();
+        assert_eq!(reader.count(), 0);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{ReadAt, Size, WriteAt};
+use std::{
+    io,
+    sync::{Mutex, PoisonError},
+};
+
+/// A wrapper that updates bytes of a source in place.
+///
+/// [`update_at`](Self::update_at) reads a range, lets a closure modify it,
+/// then writes it back. Calls to `update_at` on the same `Updatable` are
+/// serialized by a lock, so they are atomic with respect to each other, which
+/// makes it possible to update counters or bit fields from several threads.
+///
+/// Other writes are not protected: writes through
+/// [`write_at`](WriteAt::write_at), or through other handles to the same
+/// file, may be lost if they happen during an update.
+#[derive(Debug)]
+pub struct Updatable<T> {
+    inner: T,
+    lock: Mutex<()>,
+}
+
+impl<T> Updatable<T> {
+    /// Creates a new `Updatable` wrapper.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ReadAt + WriteAt> Updatable<T> {
+    /// Updates `len` bytes at `offset` in place.
+    ///
+    /// The range is read, passed to `f` to be modified, then written back.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::UnexpectedEof`] if the range is not
+    /// entirely within the source, in which case `f` is not called.
+    pub fn update_at<F>(&self, offset: u64, len: usize, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut buf = vec![0; len];
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        self.inner.read_exact_at(&mut buf, offset)?;
+        f(&mut buf);
+        self.inner.write_all_at(&buf, offset)
+    }
+}
+
+impl<T: ReadAt> ReadAt for Updatable<T> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    #[inline]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+impl<T: WriteAt> WriteAt for Updatable<T> {
+    #[inline]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.inner.write_at(buf, offset)
+    }
+
+    #[inline]
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.inner.write_all_at(buf, offset)
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn flush_tracked(&self) -> io::Result<u64> {
+        self.inner.flush_tracked()
+    }
+}
+
+impl<T: Size> Size for Updatable<T> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::TempPath, RandomAccessFile};
+    use std::{convert::TryInto, sync::Arc};
+
+    #[test]
+    fn concurrent_updates() {
+        let path = TempPath::new("update_at");
+        let file = Arc::new(Updatable::new(RandomAccessFile::from(path.create_rw())));
+        file.get_ref().set_len(16).unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let file = file.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        file.update_at(8, 8, |buf| {
+                            let n = u64::from_le_bytes(buf.try_into().unwrap());
+                            buf.copy_from_slice(&(n + 1).to_le_bytes());
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(file.read_array_at::<8>(8).unwrap(), 800u64.to_le_bytes());
+        let err = file.update_at(12, 8, |_| unreachable!()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
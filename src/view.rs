@@ -0,0 +1,211 @@
+use crate::{offset::add_base, out_of_range_error, ReadAt, Size, WriteAt};
+use std::{
+    io,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A window into a source, which can be used as a file of its own.
+///
+/// The view starts at a base offset of the inner source, and has a length,
+/// which can grow up to a maximum length. Offsets are relative to the base,
+/// and reads are clamped to the length of the view.
+///
+/// # Growth
+///
+/// Writing past the end of the view grows it up to the end of the write.
+/// Writes that end after the maximum length fail with an error of kind
+/// [`io::ErrorKind::Other`], and a call to [`write_at`](WriteAt::write_at)
+/// that starts before it writes the bytes up to it and returns a short count.
+///
+/// The inner source grows as needed when the view does, but is never
+/// truncated: the bytes of the view between its old end and the start of a
+/// write are the ones of the inner source, or zeros if the write extended
+/// it. The size of the view is its length, even if the inner source is
+/// shorter, in which case reads of the missing part fail.
+#[derive(Debug)]
+pub struct View<T> {
+    inner: T,
+    base: u64,
+    len: AtomicU64,
+    max_len: u64,
+}
+
+impl<T> View<T> {
+    /// Creates a view of `len` bytes of `inner` starting at `base`, which may
+    /// grow up to `max_len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than `max_len`.
+    pub fn new(inner: T, base: u64, len: u64, max_len: u64) -> Self {
+        assert!(len <= max_len, "view length exceeds its maximum length");
+        Self {
+            inner,
+            base,
+            len: AtomicU64::new(len),
+            max_len,
+        }
+    }
+
+    /// Returns the offset in the inner source at which the view starts.
+    #[must_use]
+    #[inline]
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Returns the current length of the view.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the view is empty.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the length up to which the view can grow.
+    #[must_use]
+    #[inline]
+    pub fn max_len(&self) -> u64 {
+        self.max_len
+    }
+
+    /// Gets a reference to the underlying source.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwraps the underlying source.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    #[inline]
+    fn offset(&self, offset: u64) -> io::Result<u64> {
+        add_base(self.base, offset)
+    }
+}
+
+impl<T: ReadAt> ReadAt for View<T> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let left = self.len().saturating_sub(offset);
+        let len = buf.len().min(left.try_into().unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        self.inner.read_at(&mut buf[..len], self.offset(offset)?)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let len = self.len();
+        if offset.saturating_add(buf.len() as u64) > len {
+            return Err(out_of_range_error(offset, buf.len(), len));
+        }
+        self.inner.read_exact_at(buf, self.offset(offset)?)
+    }
+}
+
+impl<T: WriteAt> WriteAt for View<T> {
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let left = self.max_len.saturating_sub(offset);
+        let len = buf.len().min(left.try_into().unwrap_or(usize::MAX));
+        if len == 0 && !buf.is_empty() {
+            return Err(max_len_exceeded());
+        }
+
+        let written = self.inner.write_at(&buf[..len], self.offset(offset)?)?;
+        self.len
+            .fetch_max(offset + written as u64, Ordering::AcqRel);
+        Ok(written)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let end = offset.saturating_add(buf.len() as u64);
+        if end > self.max_len {
+            return Err(max_len_exceeded());
+        }
+
+        self.inner.write_all_at(buf, self.offset(offset)?)?;
+        self.len.fetch_max(end, Ordering::AcqRel);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn flush_tracked(&self) -> io::Result<u64> {
+        let durable = self.inner.flush_tracked()?;
+        Ok(durable.saturating_sub(self.base).min(self.len()))
+    }
+}
+
+impl<T> Size for View<T> {
+    #[inline]
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<u64> {
+        Some(self.len())
+    }
+}
+
+#[cold]
+fn max_len_exceeded() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "view maximum length exceeded")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MemFile;
+
+    #[test]
+    fn grow() {
+        let file = MemFile::default();
+        file.write_all_at(b"Hello World!", 0).unwrap();
+
+        let view = View::new(&file, 6, 5, 10);
+        let mut buf = [0; 10];
+        assert_eq!(view.read_at(&mut buf, 0).unwrap(), 5);
+        assert_eq!(&buf[..5], b"World");
+
+        view.write_all_at(b"??", 6).unwrap();
+        assert_eq!(view.len(), 8);
+        view.read_exact_at(&mut buf[..8], 0).unwrap();
+        assert_eq!(&buf[..8], b"World!??");
+        assert_eq!(file.contents(), b"Hello World!??");
+
+        assert_eq!(view.write_at(b"Rust", 8).unwrap(), 2);
+        assert_eq!(view.size().unwrap(), 10);
+        assert_eq!(view.flush_tracked().unwrap(), 10);
+        view.read_exact_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"World!??Ru");
+    }
+
+    #[test]
+    fn max_len() {
+        let file = MemFile::default();
+        let view = View::new(&file, 4, 0, 8);
+
+        let err = view.write_all_at(b"Hello World!", 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(view.write_at(b"!", 8).is_err());
+        assert!(view.is_empty());
+        assert!(file.contents().is_empty());
+
+        let mut buf = [0; 1];
+        let err = view.read_exact_at(&mut buf, 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
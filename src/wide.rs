@@ -0,0 +1,225 @@
+use crate::ReadAt;
+use std::{convert::TryFrom, io};
+
+/// The `ReadAtWide` trait is like [`ReadAt`], but with 128-bit offsets.
+///
+/// Offsets that do not fit in a `u64` are only meaningful for custom backends,
+/// such as sharded object stores. Files and in-memory buffers are addressed
+/// with 64-bit offsets, so they behave as if they ended before such offsets.
+pub trait ReadAtWide {
+    /// Reads a number of bytes starting from a given offset.
+    ///
+    /// Returns the number of bytes read. Similarly to [`ReadAt::read_at`], it
+    /// is not an error to return with a short read.
+    fn read_at_wide(&self, buf: &mut [u8], offset: u128) -> io::Result<usize>;
+
+    /// Reads the exact number of byte required to fill buf from the given
+    /// offset.
+    ///
+    /// # Errors
+    ///
+    /// This function has the same error semantics as
+    /// [`ReadAt::read_exact_at`].
+    fn read_exact_at_wide(&self, mut buf: &mut [u8], mut offset: u128) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at_wide(buf, offset) {
+                Ok(0) => break,
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    offset += n as u128;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if buf.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::fill_buffer_error())
+        }
+    }
+}
+
+impl ReadAtWide for [u8] {
+    #[inline]
+    fn read_at_wide(&self, buf: &mut [u8], offset: u128) -> io::Result<usize> {
+        match u64::try_from(offset) {
+            Ok(offset) => self.read_at(buf, offset),
+            Err(_) => Ok(0),
+        }
+    }
+}
+
+impl ReadAtWide for Vec<u8> {
+    #[inline]
+    fn read_at_wide(&self, buf: &mut [u8], offset: u128) -> io::Result<usize> {
+        (**self).read_at_wide(buf, offset)
+    }
+}
+
+/// An adapter that implements `std::io` traits over a [`ReadAtWide`] source.
+///
+/// This is the 128-bit counterpart of [`Adapter`](crate::Adapter). As
+/// [`io::Seek`] uses 64-bit positions, seeking to an offset that does not fit
+/// in a `u64` fails: use [`set_offset`](Self::set_offset) instead.
+#[derive(Debug, Clone, Copy)]
+pub struct WideAdapter<T: ?Sized> {
+    offset: u128,
+    inner: T,
+}
+
+impl<T> WideAdapter<T> {
+    /// Creates a new `WideAdapter`.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self { offset: 0, inner }
+    }
+
+    /// Creates a new `WideAdapter` with its cursor at the given offset.
+    #[inline]
+    pub fn with_offset(inner: T, offset: u128) -> Self {
+        Self { offset, inner }
+    }
+
+    /// Unwraps the inner stream.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ?Sized> WideAdapter<T> {
+    /// Returns the offset used when reading the stream.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u128 {
+        self.offset
+    }
+
+    /// Moves the cursor to the given offset.
+    #[inline]
+    pub fn set_offset(&mut self, offset: u128) {
+        self.offset = offset;
+    }
+
+    /// Gets a reference to the underlying stream.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying stream.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T> io::Read for WideAdapter<T>
+where
+    T: ReadAtWide + ?Sized,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read_at_wide(buf, self.offset)?;
+        self.offset += read as u128;
+        Ok(read)
+    }
+
+    #[inline]
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let ret = self.inner.read_exact_at_wide(buf, self.offset);
+        if ret.is_ok() {
+            self.offset += buf.len() as u128;
+        }
+        ret
+    }
+}
+
+impl<T> io::Seek for WideAdapter<T>
+where
+    T: ?Sized,
+{
+    /// Note: seeking to an offset relative to the end of a stream is
+    /// unsupported, and so is seeking to an offset that does not fit in a
+    /// `u64`.
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let offset = match pos {
+            io::SeekFrom::Start(p) => p as u128,
+            io::SeekFrom::Current(p) => {
+                let offset = if p < 0 {
+                    self.offset.checked_sub(p.unsigned_abs() as u128)
+                } else {
+                    self.offset.checked_add(p as u128)
+                };
+                offset.ok_or_else(wide_seek_error)?
+            }
+            io::SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "unsupported seek to end of stream",
+                ))
+            }
+        };
+
+        let ret = u64::try_from(offset).map_err(|_| wide_seek_error())?;
+        self.offset = offset;
+        Ok(ret)
+    }
+}
+
+#[cold]
+fn wide_seek_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "invalid seek to a negative or non 64-bit position",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Seek};
+
+    const BASE: u128 = 1 << 70;
+
+    /// A backend with data far beyond 64-bit offsets.
+    struct Far(Vec<u8>);
+
+    impl ReadAtWide for Far {
+        fn read_at_wide(&self, buf: &mut [u8], offset: u128) -> io::Result<usize> {
+            match offset.checked_sub(BASE) {
+                Some(offset) => self.0.read_at_wide(buf, offset),
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn vec_wide_offsets() {
+        let data = b"Hello World!".to_vec();
+        let mut buf = [0; 5];
+
+        data.read_exact_at_wide(&mut buf, 6).unwrap();
+        assert_eq!(&buf, b"World");
+        assert_eq!(data.read_at_wide(&mut buf, BASE).unwrap(), 0);
+    }
+
+    #[test]
+    fn wide_adapter() {
+        let mut adapter = WideAdapter::with_offset(Far(b"Hello World!".to_vec()), BASE);
+        let mut buf = [0; 6];
+
+        adapter.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello ");
+        assert_eq!(adapter.offset(), BASE + 6);
+        assert!(adapter.seek(io::SeekFrom::Current(-6)).is_err());
+
+        adapter.set_offset(BASE + 11);
+        assert_eq!(adapter.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], b'!');
+
+        assert_eq!(adapter.seek(io::SeekFrom::Start(3)).unwrap(), 3);
+        assert_eq!(adapter.read(&mut buf).unwrap(), 0);
+    }
+}